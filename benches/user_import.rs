@@ -0,0 +1,88 @@
+// Allocations and time for a bulk user import, borrowed vs owned parsing
+//
+// cargo bench --bench user_import
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use rust_example::users::{self, Email, User, UserRef, Username};
+
+// counts every allocation made through the global allocator
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const USERS: usize = 10_000;
+
+// what an import without UserRef looks like: a String per field
+fn parse_owned(input: &str) -> Vec<User> {
+    input
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<String> = line.split(',').map(String::from).collect();
+            User {
+                username: Username::parse(&fields[0]).unwrap(),
+                email: Email::parse(&fields[1]).unwrap(),
+                sign_in_count: fields[2].parse().unwrap(),
+                active: fields[3] == "true",
+            }
+        })
+        .collect()
+}
+
+fn parse_borrowed(input: &str) -> Vec<UserRef<'_>> {
+    users::parse_csv(input).map(Result::unwrap).collect()
+}
+
+fn measure<T>(name: &str, run: impl Fn() -> Vec<T>) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let parsed = run();
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert_eq!(parsed.len(), USERS);
+    println!(
+        "{:<22} {:>8} allocations {:>6.2} per user {:>10.2?}",
+        name,
+        allocations,
+        allocations as f64 / USERS as f64,
+        elapsed
+    );
+}
+
+fn main() {
+    let mut input = String::from(users::CSV_HEADER);
+    for i in 0..USERS {
+        input.push_str(&format!("\nuser{},user{}@example.com,{},true", i, i, i % 7));
+    }
+
+    println!("importing {} users", USERS);
+    measure("owned fields", || parse_owned(&input));
+    measure("UserRef", || parse_borrowed(&input));
+    measure("UserRef then to_owned", || {
+        parse_borrowed(&input)
+            .iter()
+            .map(UserRef::to_owned)
+            .collect()
+    });
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+fn fix_incorrect_order() {
+    cook_order();
+    super::serve_order();
+    // since relationship between back_of_house and serve_order
+    // are likely to stay together -> less updates to future code
+}
+
+fn cook_order() {}
+
+/*
+Structs Public
+- need to specify public fields
+- associated methods are needed if any private
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Breakfast {
+    pub toast: String,
+    seasonal_fruit: String, // by default private
+}
+
+// Needs to provide a public associated function (instance Breakfast)
+// since unable to create instance with private field
+impl Breakfast {
+    pub fn summer(toast: &str) -> Breakfast {
+        Breakfast {
+            toast: String::from(toast),
+            seasonal_fruit: String::from("peaches"),
+        }
+    }
+
+    pub fn seasonal_fruit(&self) -> &str {
+        &self.seasonal_fruit
+    }
+}
+/*
+Enum Public
+- if enumal public: all variants are also public
+wouldn't be useful if private
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Appetizer {
+    Soup,
+    Salad,
+}
+
+/// Anything the kitchen can cook
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dish {
+    Appetizer(Appetizer),
+    Breakfast(Breakfast),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MenuItem {
+    // what staff type when taking an order, i.e. "soup"
+    pub name: String,
+    pub dish: Dish,
+    pub price_cents: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Menu {
+    items: Vec<MenuItem>,
+}
+
+impl Menu {
+    pub fn new() -> Menu {
+        Menu { items: Vec::new() }
+    }
+
+    // replaces an item with the same name
+    pub fn add(&mut self, name: &str, dish: Dish, price_cents: u32) {
+        self.items.retain(|i| i.name != name);
+        self.items.push(MenuItem {
+            name: String::from(name),
+            dish,
+            price_cents,
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MenuItem> {
+        self.items.iter().find(|i| i.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MenuItem> {
+        self.items.iter()
+    }
+}
+
+// what the house serves when nothing else is configured
+impl Default for Menu {
+    fn default() -> Menu {
+        let mut menu = Menu::new();
+        menu.add("soup", Dish::Appetizer(Appetizer::Soup), 550);
+        menu.add("salad", Dish::Appetizer(Appetizer::Salad), 650);
+        menu.add("rye", Dish::Breakfast(Breakfast::summer("Rye")), 900);
+        menu.add("wheat", Dish::Breakfast(Breakfast::summer("Wheat")), 900);
+        menu
+    }
+}
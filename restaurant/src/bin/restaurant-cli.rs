@@ -0,0 +1,227 @@
+/*
+Front-of-house terminal client
+
+Every run loads the state file, applies one command and saves it again,
+so staff can drive the floor from a shell:
+
+    restaurant-cli add-table 4
+    restaurant-cli add-party Ana 2
+    restaurant-cli seat 1
+    restaurant-cli order 1 soup rye
+    restaurant-cli serve 1
+    restaurant-cli pay 1
+
+State file: `--state <path>`, else $RESTAURANT_STATE, else ./restaurant.json
+ */
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use restaurant::hosting::{PartyId, TableId};
+use restaurant::reports::Report;
+use restaurant::serving::{Cents, OrderId};
+use restaurant::{snapshot, Error, Restaurant};
+
+const USAGE: &str = "\
+usage: restaurant-cli [--state <path>] <command> [args]
+
+commands:
+  status                     tables, waitlist and open orders
+  menu                       list menu items
+  add-table <seats>          add a table to the floor
+  add-party <name> <size>    put a party on the waitlist
+  seat <party> [table]       seat a waiting party (best fit if no table)
+  order <table> <item>...    place an order for a seated table
+  serve <order>              mark an order as served
+  check <table>              print the running check
+  pay <table>                print the final check and free the table
+  report                     covers, table turns, average check, top sellers
+  events-csv                 the event log as CSV
+  listen [port]              run the JSON API on localhost (http builds)";
+
+enum Command {
+    Status,
+    Menu,
+    AddTable(u32),
+    AddParty(String, u32),
+    Seat(PartyId, Option<TableId>),
+    Order(TableId, Vec<String>),
+    Serve(OrderId),
+    Check(TableId),
+    Pay(TableId),
+    Report,
+    EventsCsv,
+}
+
+impl Command {
+    fn parse(args: &[String]) -> Result<Command, String> {
+        let (name, rest) = args.split_first().ok_or("missing command")?;
+        let command = match (name.as_str(), rest) {
+            ("status", []) => Command::Status,
+            ("menu", []) => Command::Menu,
+            ("add-table", [seats]) => Command::AddTable(number(seats)?),
+            ("add-party", [name, size]) => Command::AddParty(name.clone(), number(size)?),
+            ("seat", [party]) => Command::Seat(PartyId(number(party)?), None),
+            ("seat", [party, table]) => {
+                Command::Seat(PartyId(number(party)?), Some(TableId(number(table)?)))
+            }
+            ("order", [table, items @ ..]) if !items.is_empty() => {
+                Command::Order(TableId(number(table)?), items.to_vec())
+            }
+            ("serve", [order]) => Command::Serve(OrderId(number(order)?)),
+            ("check", [table]) => Command::Check(TableId(number(table)?)),
+            ("pay", [table]) => Command::Pay(TableId(number(table)?)),
+            ("report", []) => Command::Report,
+            ("events-csv", []) => Command::EventsCsv,
+            _ => return Err(format!("bad arguments for `{}`", name)),
+        };
+        Ok(command)
+    }
+
+    // read only commands don't rewrite the state file
+    fn mutates(&self) -> bool {
+        !matches!(
+            self,
+            Command::Status
+                | Command::Menu
+                | Command::Check(_)
+                | Command::Report
+                | Command::EventsCsv
+        )
+    }
+
+    fn run(self, r: &mut Restaurant) -> Result<String, Error> {
+        let out = match self {
+            Command::Status => status(r),
+            Command::Menu => r
+                .menu()
+                .iter()
+                .map(|i| format!("{:<10} {:>8}", i.name, Cents(i.price_cents)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Command::AddTable(seats) => format!("added {}", r.add_table(seats)),
+            Command::AddParty(name, size) => {
+                let id = r.add_party(&name, size)?;
+                let ahead = r.waitlist().position(id).unwrap_or(0);
+                format!("added {} ({} ahead)", id, ahead)
+            }
+            Command::Seat(party, table) => {
+                let table = r.seat_party(party, table)?;
+                format!("seated {} at {}", party, table)
+            }
+            Command::Order(table, items) => {
+                let items: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+                format!("placed {}", r.place_order(table, &items)?)
+            }
+            Command::Serve(order) => {
+                r.serve_order(order)?;
+                format!("served {}", order)
+            }
+            Command::Check(table) => r.check(table)?.to_string(),
+            Command::Pay(table) => format!("{}\npaid", r.close_table(table)?),
+            Command::Report => Report::from_log(r.events()).to_string(),
+            Command::EventsCsv => r.events().to_csv().trim_end().to_string(),
+        };
+        Ok(out)
+    }
+}
+
+fn number(s: &str) -> Result<u32, String> {
+    s.parse().map_err(|_| format!("`{}` is not a number", s))
+}
+
+fn status(r: &Restaurant) -> String {
+    let mut lines = vec![String::from("tables:")];
+    for t in r.tables().iter() {
+        match &t.party {
+            Some(p) => lines.push(format!(
+                "  {} ({} seats): {} x{}",
+                t.id, t.seats, p.name, p.size
+            )),
+            None => lines.push(format!("  {} ({} seats): free", t.id, t.seats)),
+        }
+    }
+    lines.push(String::from("waitlist:"));
+    for p in r.waitlist().iter() {
+        lines.push(format!("  {}: {} x{}", p.id, p.name, p.size));
+    }
+    lines.push(String::from("open orders:"));
+    for o in r.open_orders() {
+        let items: Vec<&str> = o.lines.iter().map(|l| l.item.as_str()).collect();
+        lines.push(format!("  {} for {}: {}", o.id, o.table, items.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut state = env::var_os("RESTAURANT_STATE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("restaurant.json"));
+    if args.first().map(|a| a.as_str()) == Some("--state") {
+        if args.len() < 2 {
+            fail(2, "--state needs a path");
+        }
+        state = PathBuf::from(args.remove(1));
+        args.remove(0);
+    }
+
+    #[cfg(feature = "http")]
+    {
+        if let Some("listen") = args.first().map(|a| a.as_str()) {
+            return serve_http(state, &args[1..]);
+        }
+    }
+
+    let command = match Command::parse(&args) {
+        Ok(c) => c,
+        Err(e) => fail(2, &format!("{}\n\n{}", e, USAGE)),
+    };
+
+    let mut r = load(&state);
+
+    let mutates = command.mutates();
+    match command.run(&mut r) {
+        Ok(out) => println!("{}", out),
+        Err(e) => fail(1, &e.to_string()),
+    }
+    if mutates {
+        if let Err(e) = snapshot::save(&r, &state) {
+            fail(1, &format!("{}: {}", state.display(), e));
+        }
+    }
+}
+
+// first run starts from an empty floor with the default menu
+fn load(state: &Path) -> Restaurant {
+    if state.exists() {
+        snapshot::load(state).unwrap_or_else(|e| fail(1, &format!("{}: {}", state.display(), e)))
+    } else {
+        Restaurant::default()
+    }
+}
+
+#[cfg(feature = "http")]
+fn serve_http(state: PathBuf, args: &[String]) {
+    let port = match args {
+        [] => 7878,
+        [port] => port
+            .parse()
+            .unwrap_or_else(|_| fail(2, &format!("`{}` is not a port", port))),
+        _ => fail(2, USAGE),
+    };
+    let server = restaurant::http::Server::bind_localhost(port, load(&state))
+        .unwrap_or_else(|e| fail(1, &e.to_string()))
+        .with_state_file(state);
+    if let Ok(addr) = server.local_addr() {
+        eprintln!("listening on http://{}", addr);
+    }
+    if let Err(e) = server.serve() {
+        fail(1, &e.to_string());
+    }
+}
+
+fn fail(code: i32, msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(code);
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+use std::io;
+
+use crate::front_of_house::hosting::{PartyId, TableId};
+use crate::front_of_house::reservations::ReservationId;
+use crate::front_of_house::serving::OrderId;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownParty(PartyId),
+    UnknownTable(TableId),
+    UnknownOrder(OrderId),
+    UnknownMenuItem(String),
+    InvalidPartySize,
+    EmptyOrder,
+    TableOccupied(TableId),
+    TableTooSmall {
+        table: TableId,
+        seats: u32,
+        size: u32,
+    },
+    NoTableAvailable {
+        size: u32,
+    },
+    TableNotSeated(TableId),
+    OrderAlreadyServed(OrderId),
+    OrdersOutstanding(TableId),
+    UnknownReservation(ReservationId),
+    InvalidDuration,
+    // start + duration doesn't fit in a u64
+    InvalidReservation,
+    ReservationInPast,
+    // not enough tables for everyone booked around `start`
+    Overbooked {
+        start: u64,
+    },
+    // past the grace period, the booking was given up
+    ReservationExpired(ReservationId),
+    // cancelled or already checked in
+    ReservationClosed(ReservationId),
+    Io(io::Error),
+    // snapshot text could not be parsed or written
+    Format(String),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownParty(id) => write!(f, "no such {}", id),
+            Error::UnknownTable(id) => write!(f, "no such {}", id),
+            Error::UnknownOrder(id) => write!(f, "no such {}", id),
+            Error::UnknownMenuItem(name) => write!(f, "`{}` is not on the menu", name),
+            Error::InvalidPartySize => write!(f, "a party needs at least one guest"),
+            Error::EmptyOrder => write!(f, "an order needs at least one item"),
+            Error::TableOccupied(id) => write!(f, "{} is already occupied", id),
+            Error::TableTooSmall { table, seats, size } => write!(
+                f,
+                "{} only seats {}, party has {} guests",
+                table, seats, size
+            ),
+            Error::NoTableAvailable { size } => {
+                write!(f, "no free table for {} guests", size)
+            }
+            Error::TableNotSeated(id) => write!(f, "nobody is seated at {}", id),
+            Error::OrderAlreadyServed(id) => write!(f, "{} was already served", id),
+            Error::OrdersOutstanding(id) => {
+                write!(f, "{} still has orders waiting on the kitchen", id)
+            }
+            Error::UnknownReservation(id) => write!(f, "no such {}", id),
+            Error::InvalidDuration => write!(f, "a reservation needs a duration"),
+            Error::InvalidReservation => write!(f, "a reservation can't end that late"),
+            Error::ReservationInPast => write!(f, "can't book a time that has passed"),
+            Error::Overbooked { start } => {
+                write!(f, "no table free for that party at {}", start)
+            }
+            Error::ReservationExpired(id) => {
+                write!(f, "{} is past its grace period", id)
+            }
+            Error::ReservationClosed(id) => {
+                write!(f, "{} was cancelled or already checked in", id)
+            }
+            Error::Io(e) => write!(f, "i/o error: {}", e),
+            Error::Format(msg) => write!(f, "bad snapshot: {}", msg),
+            Error::UnsupportedVersion(v) => {
+                write!(f, "snapshot version {} is not supported", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::front_of_house::hosting::{PartyId, TableId};
+use crate::front_of_house::reservations::ReservationId;
+use crate::front_of_house::serving::{OrderId, OrderLine};
+
+/// Something that happened on the floor, stamped with when it happened
+/// (seconds since the unix epoch)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub at: u64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum EventKind {
+    /* Hosting */
+    PartyAdded {
+        party: PartyId,
+        size: u32,
+    },
+    PartySeated {
+        party: PartyId,
+        table: TableId,
+        size: u32,
+    },
+    ReservationBooked {
+        reservation: ReservationId,
+        size: u32,
+        start: u64,
+    },
+    ReservationArrived {
+        reservation: ReservationId,
+        party: PartyId,
+    },
+    ReservationCancelled {
+        reservation: ReservationId,
+    },
+    // didn't check in within the grace period
+    ReservationNoShow {
+        reservation: ReservationId,
+    },
+    /* Kitchen */
+    // ticket sent to the kitchen
+    OrderPlaced {
+        order: OrderId,
+        table: TableId,
+        lines: Vec<OrderLine>,
+    },
+    // kitchen is done and the food is on the table
+    OrderServed {
+        order: OrderId,
+        table: TableId,
+    },
+    /* Billing */
+    CheckClosed {
+        party: PartyId,
+        table: TableId,
+        total_cents: u32,
+    },
+}
+
+impl EventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::PartyAdded { .. } => "party_added",
+            EventKind::PartySeated { .. } => "party_seated",
+            EventKind::ReservationBooked { .. } => "reservation_booked",
+            EventKind::ReservationArrived { .. } => "reservation_arrived",
+            EventKind::ReservationCancelled { .. } => "reservation_cancelled",
+            EventKind::ReservationNoShow { .. } => "reservation_no_show",
+            EventKind::OrderPlaced { .. } => "order_placed",
+            EventKind::OrderServed { .. } => "order_served",
+            EventKind::CheckClosed { .. } => "check_closed",
+        }
+    }
+}
+
+/// Append-only record of everything the restaurant did, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EventLog {
+    events: Vec<Event>,
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::default()
+    }
+
+    // only the restaurant itself records what happened
+    pub(crate) fn push(&mut self, at: u64, kind: EventKind) {
+        self.events.push(Event { at, kind });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// One row per event:
+    /// `at,event,party,table,order,size,items,total_cents,reservation`,
+    /// columns that don't apply to an event are left empty and
+    /// `items` is the menu names separated by `;`
+    pub fn write_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "at,event,party,table,order,size,items,total_cents,reservation"
+        )?;
+        for e in &self.events {
+            let mut row = Row::default();
+            match &e.kind {
+                EventKind::PartyAdded { party, size } => {
+                    row.party = Some(party.0);
+                    row.size = Some(*size);
+                }
+                EventKind::PartySeated { party, table, size } => {
+                    row.party = Some(party.0);
+                    row.table = Some(table.0);
+                    row.size = Some(*size);
+                }
+                EventKind::ReservationBooked {
+                    reservation, size, ..
+                } => {
+                    row.reservation = Some(reservation.0);
+                    row.size = Some(*size);
+                }
+                EventKind::ReservationArrived { reservation, party } => {
+                    row.reservation = Some(reservation.0);
+                    row.party = Some(party.0);
+                }
+                EventKind::ReservationCancelled { reservation }
+                | EventKind::ReservationNoShow { reservation } => {
+                    row.reservation = Some(reservation.0);
+                }
+                EventKind::OrderPlaced {
+                    order,
+                    table,
+                    lines,
+                } => {
+                    row.order = Some(order.0);
+                    row.table = Some(table.0);
+                    let items: Vec<&str> = lines.iter().map(|l| l.item.as_str()).collect();
+                    row.items = items.join(";");
+                }
+                EventKind::OrderServed { order, table } => {
+                    row.order = Some(order.0);
+                    row.table = Some(table.0);
+                }
+                EventKind::CheckClosed {
+                    party,
+                    table,
+                    total_cents,
+                } => {
+                    row.party = Some(party.0);
+                    row.table = Some(table.0);
+                    row.total_cents = Some(*total_cents);
+                }
+            }
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{}",
+                e.at,
+                e.kind.name(),
+                cell(row.party),
+                cell(row.table),
+                cell(row.order),
+                cell(row.size),
+                quote(&row.items),
+                cell(row.total_cents),
+                cell(row.reservation)
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = Vec::new();
+        self.write_csv(&mut out)
+            .expect("writing to a Vec can't fail");
+        String::from_utf8(out).expect("csv is built from strings")
+    }
+}
+
+#[derive(Default)]
+struct Row {
+    party: Option<u32>,
+    table: Option<u32>,
+    order: Option<u32>,
+    size: Option<u32>,
+    items: String,
+    total_cents: Option<u32>,
+    reservation: Option<u32>,
+}
+
+fn cell(value: Option<u32>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+// RFC 4180: quote fields holding a separator, quote or newline
+fn quote(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
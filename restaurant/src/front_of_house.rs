@@ -1,3 +1,4 @@
-pub mod hosting {
-    pub fn add_to_waitlist() {}
-}
\ No newline at end of file
+// submodules live in src/front_of_house/<name>.rs
+pub mod hosting;
+pub mod reservations;
+pub mod serving;
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub fn add_to_waitlist() {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PartyId(pub u32);
+
+impl fmt::Display for PartyId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "party {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TableId(pub u32);
+
+impl fmt::Display for TableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "table {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Party {
+    pub id: PartyId,
+    pub name: String,
+    pub size: u32,
+}
+
+/// Parties waiting for a table, first come first served
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Waitlist {
+    parties: VecDeque<Party>,
+}
+
+impl Waitlist {
+    pub fn new() -> Waitlist {
+        Waitlist::default()
+    }
+
+    pub fn push(&mut self, party: Party) {
+        self.parties.push_back(party);
+    }
+
+    // skips the line, i.e. a reservation checking in
+    pub fn push_front(&mut self, party: Party) {
+        self.parties.push_front(party);
+    }
+
+    pub fn get(&self, id: PartyId) -> Option<&Party> {
+        self.parties.iter().find(|p| p.id == id)
+    }
+
+    // 0 is the next party to be seated
+    pub fn position(&self, id: PartyId) -> Option<usize> {
+        self.parties.iter().position(|p| p.id == id)
+    }
+
+    pub fn remove(&mut self, id: PartyId) -> Option<Party> {
+        let idx = self.position(id)?;
+        self.parties.remove(idx)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Party> {
+        self.parties.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parties.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parties.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Table {
+    pub id: TableId,
+    pub seats: u32,
+    // party currently sitting here
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub party: Option<Party>,
+}
+
+impl Table {
+    pub fn is_free(&self) -> bool {
+        self.party.is_none()
+    }
+}
+
+/// Floor plan: every table and who is sitting at it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TableMap {
+    tables: Vec<Table>,
+}
+
+impl TableMap {
+    pub fn new() -> TableMap {
+        TableMap::default()
+    }
+
+    pub fn add_table(&mut self, seats: u32) -> TableId {
+        let next = self.tables.iter().map(|t| t.id.0).max().unwrap_or(0) + 1;
+        let id = TableId(next);
+        self.tables.push(Table {
+            id,
+            seats,
+            party: None,
+        });
+        id
+    }
+
+    pub fn get(&self, id: TableId) -> Option<&Table> {
+        self.tables.iter().find(|t| t.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: TableId) -> Option<&mut Table> {
+        self.tables.iter_mut().find(|t| t.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Table> {
+        self.tables.iter()
+    }
+
+    // total number of seats on the floor
+    pub fn capacity(&self) -> u32 {
+        self.tables.iter().map(|t| t.seats).sum()
+    }
+
+    /// Smallest free table that fits `size` guests
+    pub fn best_fit(&self, size: u32) -> Option<TableId> {
+        self.tables
+            .iter()
+            .filter(|t| t.is_free() && t.seats >= size)
+            .min_by_key(|t| (t.seats, t.id))
+            .map(|t| t.id)
+    }
+
+    pub fn table_of(&self, party: PartyId) -> Option<TableId> {
+        self.tables
+            .iter()
+            .find(|t| t.party.as_ref().map(|p| p.id) == Some(party))
+            .map(|t| t.id)
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::front_of_house::hosting::PartyId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReservationId(pub u32);
+
+impl fmt::Display for ReservationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "reservation {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReservationStatus {
+    Booked,
+    // checked in and handed to the waitlist as this party
+    Arrived(PartyId),
+    Cancelled,
+    NoShow,
+}
+
+/// A future booking; times are unix seconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reservation {
+    pub id: ReservationId,
+    pub name: String,
+    pub size: u32,
+    pub start: u64,
+    pub duration: u64,
+    pub status: ReservationStatus,
+}
+
+impl Reservation {
+    /// Saturates rather than overflowing; `book` rejects bookings that
+    /// would, but a snapshot could still hold one
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.duration)
+    }
+
+    /// Still holding a table at `now`: booked and not past the grace period
+    pub fn is_active(&self, now: u64, policy: &ReservationPolicy) -> bool {
+        self.status == ReservationStatus::Booked
+            && now <= self.start.saturating_add(policy.grace_period)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReservationPolicy {
+    // how late a booking can check in before it's a no-show
+    pub grace_period: u64,
+    // how long a walk-in is expected to hold a table
+    pub walk_in_turn: u64,
+}
+
+impl Default for ReservationPolicy {
+    fn default() -> ReservationPolicy {
+        ReservationPolicy {
+            grace_period: 15 * 60,
+            walk_in_turn: 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reservations {
+    next_id: u32,
+    pub policy: ReservationPolicy,
+    bookings: Vec<Reservation>,
+}
+
+impl Default for Reservations {
+    fn default() -> Reservations {
+        Reservations {
+            next_id: 1,
+            policy: ReservationPolicy::default(),
+            bookings: Vec::new(),
+        }
+    }
+}
+
+impl Reservations {
+    pub fn get(&self, id: ReservationId) -> Option<&Reservation> {
+        self.bookings.iter().find(|r| r.id == id)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: ReservationId) -> Option<&mut Reservation> {
+        self.bookings.iter_mut().find(|r| r.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Reservation> {
+        self.bookings.iter()
+    }
+
+    /// Bookings still holding a table at `now`, soonest first
+    pub fn active(&self, now: u64) -> Vec<&Reservation> {
+        let mut active: Vec<&Reservation> = self
+            .bookings
+            .iter()
+            .filter(|r| r.is_active(now, &self.policy))
+            .collect();
+        active.sort_by_key(|r| (r.start, r.id));
+        active
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        name: &str,
+        size: u32,
+        start: u64,
+        duration: u64,
+    ) -> ReservationId {
+        let id = ReservationId(self.next_id);
+        self.next_id += 1;
+        self.bookings.push(Reservation {
+            id,
+            name: String::from(name),
+            size,
+            start,
+            duration,
+            status: ReservationStatus::Booked,
+        });
+        id
+    }
+}
+
+/// A party holding some table over `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Slot {
+    pub size: u32,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// A table and the earliest time it's free
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Availability {
+    pub seats: u32,
+    pub free_from: u64,
+}
+
+/// Whether, at every moment, each slot in progress can have its own table
+///
+/// Who holds a table only changes when a slot starts and tables only ever
+/// free up, so checking the moment each slot starts covers every moment.
+/// At each of those, the largest party takes the smallest table that fits,
+/// which never blocks a seating that would otherwise have worked.
+///
+/// Moments are checked independently, so this is a capacity check: it
+/// doesn't pin a party to one table for its whole stay, that's left to the
+/// host when seating.
+pub(crate) fn feasible(tables: &[Availability], slots: &[Slot]) -> bool {
+    slots.iter().all(|at| {
+        let t = at.start;
+        let mut parties: Vec<u32> = slots
+            .iter()
+            .filter(|s| s.start <= t && t < s.end)
+            .map(|s| s.size)
+            .collect();
+        parties.sort_unstable_by(|a, b| b.cmp(a));
+        let mut free: Vec<u32> = tables
+            .iter()
+            .filter(|a| a.free_from <= t)
+            .map(|a| a.seats)
+            .collect();
+        free.sort_unstable();
+
+        parties
+            .iter()
+            .all(|&size| match free.iter().position(|&seats| seats >= size) {
+                Some(idx) => {
+                    free.remove(idx);
+                    true
+                }
+                None => false,
+            })
+    })
+}
+
+/// Earliest time at or after `now` a party of `size` can hold a table for
+/// `turn` seconds without breaking any existing slot
+pub(crate) fn earliest_start(
+    tables: &[Availability],
+    slots: &[Slot],
+    size: u32,
+    now: u64,
+    turn: u64,
+) -> Option<u64> {
+    if !tables.iter().any(|a| a.seats >= size) {
+        return None;
+    }
+    // seating only gets easier when a table frees up or a slot ends
+    let mut candidates: Vec<u64> = tables
+        .iter()
+        .map(|a| a.free_from)
+        .chain(slots.iter().map(|s| s.end))
+        .filter(|&t| t > now)
+        .collect();
+    candidates.push(now);
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut trial = slots.to_vec();
+    candidates.into_iter().find(|&start| {
+        trial.push(Slot {
+            size,
+            start,
+            end: start.saturating_add(turn),
+        });
+        let ok = feasible(tables, &trial);
+        trial.pop();
+        ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(seats: u32, free_from: u64) -> Availability {
+        Availability { seats, free_from }
+    }
+
+    fn slot(size: u32, start: u64, end: u64) -> Slot {
+        Slot { size, start, end }
+    }
+
+    #[test]
+    fn one_table_per_party() {
+        let tables = [table(4, 0), table(2, 0)];
+        assert!(feasible(&tables, &[slot(4, 0, 10), slot(2, 5, 15)]));
+        // two parties of 3 can't share the 4-top
+        assert!(!feasible(&tables, &[slot(3, 0, 10), slot(3, 5, 15)]));
+        // fine once the first has gone
+        assert!(feasible(&tables, &[slot(3, 0, 10), slot(3, 10, 20)]));
+    }
+
+    #[test]
+    fn greedy_leaves_big_tables_for_big_parties() {
+        let tables = [table(6, 0), table(2, 0)];
+        assert!(feasible(&tables, &[slot(2, 0, 10), slot(6, 1, 10)]));
+    }
+
+    #[test]
+    fn earliest_start_waits_for_a_table_to_free() {
+        let tables = [table(4, 30)];
+        assert_eq!(earliest_start(&tables, &[], 2, 0, 60), Some(30));
+        assert_eq!(earliest_start(&tables, &[], 5, 0, 60), None);
+    }
+
+    #[test]
+    fn earliest_start_steps_around_bookings() {
+        let tables = [table(4, 0)];
+        // booked from 30 to 90: a 60s walk-in can't start now, only at 90
+        let booked = [slot(4, 30, 90)];
+        assert_eq!(earliest_start(&tables, &booked, 2, 0, 60), Some(90));
+        // a short enough turn fits before the booking
+        assert_eq!(earliest_start(&tables, &booked, 2, 0, 30), Some(0));
+    }
+}
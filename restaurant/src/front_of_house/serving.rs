@@ -0,0 +1,77 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::back_of_house::Dish;
+use crate::front_of_house::hosting::TableId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OrderId(pub u32);
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "order {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderStatus {
+    Open,
+    Served,
+}
+
+/// One menu item on an order, priced when it was ordered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderLine {
+    pub item: String,
+    pub dish: Dish,
+    pub price_cents: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: OrderId,
+    pub table: TableId,
+    pub lines: Vec<OrderLine>,
+    pub status: OrderStatus,
+}
+
+impl Order {
+    pub fn total_cents(&self) -> u32 {
+        self.lines.iter().map(|l| l.price_cents).sum()
+    }
+}
+
+/// Everything a table owes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Check {
+    pub table: TableId,
+    pub party: Option<String>,
+    pub lines: Vec<OrderLine>,
+    pub total_cents: u32,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.party {
+            Some(name) => writeln!(f, "Check for {} ({})", self.table, name)?,
+            None => writeln!(f, "Check for {}", self.table)?,
+        }
+        for line in &self.lines {
+            writeln!(f, "  {:<20} {:>8}", line.item, Cents(line.price_cents))?;
+        }
+        write!(f, "  {:<20} {:>8}", "Total", Cents(self.total_cents))
+    }
+}
+
+/// Formats an amount in cents as dollars, i.e. `$12.50`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cents(pub u32);
+
+impl fmt::Display for Cents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // pad the whole string so callers can align columns
+        f.pad(&format!("${}.{:02}", self.0 / 100, self.0 % 100))
+    }
+}
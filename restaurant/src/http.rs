@@ -0,0 +1,351 @@
+//! Small JSON API over HTTP/1.1 for the POS tablets (`http` feature)
+//!
+//! One request per connection, every body is JSON. Errors come back as
+//! `{"error": "..."}` with a 4xx status.
+//!
+//! | method | path                 | body                     | response            |
+//! |--------|----------------------|--------------------------|---------------------|
+//! | GET    | `/menu`              |                          | menu items          |
+//! | GET    | `/tables`            |                          | tables              |
+//! | POST   | `/tables`            | `{"seats": 4}`           | `{"id": 1}`         |
+//! | GET    | `/tables/{id}/check` |                          | running check       |
+//! | POST   | `/tables/{id}/pay`   |                          | final check         |
+//! | GET    | `/waitlist`          |                          | waiting parties     |
+//! | POST   | `/waitlist`          | `{"name": "Ana", "size": 2}` | `{"id": 1}`     |
+//! | POST   | `/waitlist/{id}/seat`| `{"table": 1}` or `{}`   | `{"table": 1}`      |
+//! | GET    | `/orders`            |                          | open orders         |
+//! | POST   | `/orders`            | `{"table": 1, "items": ["soup"]}` | `{"id": 1}` |
+//! | POST   | `/orders/{id}/serve` |                          | `{}`                |
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::front_of_house::hosting::{PartyId, TableId};
+use crate::front_of_house::serving::OrderId;
+use crate::snapshot;
+use crate::state::Restaurant;
+
+// anything bigger than this is not an order
+const MAX_BODY: usize = 64 * 1024;
+// request line and headers together, so a client can't make us buffer
+// one endless line
+pub const MAX_HEADER_BYTES: u64 = 8 * 1024;
+
+pub struct Server {
+    listener: TcpListener,
+    restaurant: Arc<Mutex<Restaurant>>,
+    state_file: Option<PathBuf>,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, restaurant: Restaurant) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            restaurant: Arc::new(Mutex::new(restaurant)),
+            state_file: None,
+        })
+    }
+
+    /// Only reachable from this machine
+    pub fn bind_localhost(port: u16, restaurant: Restaurant) -> io::Result<Server> {
+        Server::bind(("127.0.0.1", port), restaurant)
+    }
+
+    /// Saves a snapshot after every request that changes state
+    pub fn with_state_file(mut self, path: PathBuf) -> Server {
+        self.state_file = Some(path);
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Shared handle to the state the server is working on
+    pub fn restaurant(&self) -> Arc<Mutex<Restaurant>> {
+        Arc::clone(&self.restaurant)
+    }
+
+    /// Accepts connections forever, one thread per connection
+    pub fn serve(self) -> io::Result<()> {
+        let shared = Arc::new((self.restaurant, self.state_file));
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let (restaurant, state_file) = &*shared;
+                // a client hanging up mid-request is their problem
+                let _ = handle_connection(stream, restaurant, state_file.as_ref());
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs [`serve`](Server::serve) on a background thread
+    pub fn spawn(self) -> thread::JoinHandle<io::Result<()>> {
+        thread::spawn(move || self.serve())
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Response {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, msg: &str) -> Response {
+        Response {
+            status,
+            body: json!({ "error": msg }),
+        }
+    }
+}
+
+impl From<Error> for Response {
+    fn from(e: Error) -> Response {
+        let status = match e {
+            Error::UnknownParty(_)
+            | Error::UnknownTable(_)
+            | Error::UnknownOrder(_)
+            | Error::UnknownReservation(_) => 404,
+            Error::TableOccupied(_)
+            | Error::OrderAlreadyServed(_)
+            | Error::OrdersOutstanding(_)
+            | Error::NoTableAvailable { .. }
+            | Error::Overbooked { .. }
+            | Error::ReservationExpired(_)
+            | Error::ReservationClosed(_) => 409,
+            Error::Io(_) | Error::Format(_) | Error::UnsupportedVersion(_) => 500,
+            _ => 422,
+        };
+        Response::error(status, &e.to_string())
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    restaurant: &Mutex<Restaurant>,
+    state_file: Option<&PathBuf>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Err(response) => response,
+        Ok(request) => {
+            let mut r = restaurant.lock().unwrap_or_else(|e| e.into_inner());
+            match state_file {
+                Some(path) if request.method == "POST" => {
+                    let before = r.clone();
+                    let response = route(&request, &mut r);
+                    if response.status != 200 {
+                        // a request that failed changed nothing to save
+                        response
+                    } else {
+                        match snapshot::save(&r, path) {
+                            Ok(()) => response,
+                            Err(e) => {
+                                // keep memory and disk in step
+                                *r = before;
+                                Response::from(e)
+                            }
+                        }
+                    }
+                }
+                _ => route(&request, &mut r),
+            }
+        }
+    };
+    write_response(stream, &response)
+}
+
+// the error is the response to send back: 400, or 431 for a head over
+// MAX_HEADER_BYTES
+fn read_request<R: BufRead>(reader: R) -> Result<Request, Response> {
+    let bad = |msg: &str| Response::error(400, msg);
+
+    let mut head = reader.take(MAX_HEADER_BYTES);
+    let mut line = String::new();
+    read_head_line(&mut head, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or_else(|| bad("empty request"))?
+        .to_string();
+    let path = parts.next().ok_or_else(|| bad("missing path"))?.to_string();
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if read_head_line(&mut head, &mut header)? == 0 {
+            return Err(bad("headers ended early"));
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad("bad content-length"))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(bad("body too large"));
+    }
+    let mut body = vec![0; length];
+    head.into_inner()
+        .read_exact(&mut body)
+        .map_err(|e| bad(&e.to_string()))?;
+    Ok(Request { method, path, body })
+}
+
+fn read_head_line<R: BufRead>(
+    head: &mut io::Take<R>,
+    line: &mut String,
+) -> Result<usize, Response> {
+    let n = head
+        .read_line(line)
+        .map_err(|e| Response::error(400, &e.to_string()))?;
+    // ran out of allowance before the end of the line
+    if head.limit() == 0 && !line.ends_with('\n') {
+        return Err(Response::error(431, "request headers are too large"));
+    }
+    Ok(n)
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn route(request: &Request, r: &mut Restaurant) -> Response {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let result = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["menu"]) => Ok(to_json(r.menu())),
+        ("GET", ["tables"]) => Ok(to_json(&r.tables().iter().collect::<Vec<_>>())),
+        ("POST", ["tables"]) => {
+            body::<NewTable>(request).map(|b| json!({ "id": r.add_table(b.seats) }))
+        }
+        ("GET", ["tables", id, "check"]) => id_from(id).and_then(|id| {
+            r.check(TableId(id))
+                .map(|c| to_json(&c))
+                .map_err(Response::from)
+        }),
+        ("POST", ["tables", id, "pay"]) => id_from(id).and_then(|id| {
+            r.close_table(TableId(id))
+                .map(|c| to_json(&c))
+                .map_err(Response::from)
+        }),
+        ("GET", ["waitlist"]) => Ok(to_json(&r.waitlist().iter().collect::<Vec<_>>())),
+        ("POST", ["waitlist"]) => body::<NewParty>(request).and_then(|b| {
+            r.add_party(&b.name, b.size)
+                .map(|id| json!({ "id": id }))
+                .map_err(Response::from)
+        }),
+        ("POST", ["waitlist", id, "seat"]) => id_from(id).and_then(|id| {
+            let b = body::<Seat>(request)?;
+            r.seat_party(PartyId(id), b.table.map(TableId))
+                .map(|t| json!({ "table": t }))
+                .map_err(Response::from)
+        }),
+        ("GET", ["orders"]) => Ok(to_json(&r.open_orders().collect::<Vec<_>>())),
+        ("POST", ["orders"]) => body::<NewOrder>(request).and_then(|b| {
+            let items: Vec<&str> = b.items.iter().map(|s| s.as_str()).collect();
+            r.place_order(TableId(b.table), &items)
+                .map(|id| json!({ "id": id }))
+                .map_err(Response::from)
+        }),
+        ("POST", ["orders", id, "serve"]) => id_from(id).and_then(|id| {
+            r.serve_order(OrderId(id))
+                .map(|_| json!({}))
+                .map_err(Response::from)
+        }),
+        (_, ["menu"]) | (_, ["tables"]) | (_, ["waitlist"]) | (_, ["orders"]) => {
+            Err(Response::error(405, "method not allowed"))
+        }
+        _ => Err(Response::error(404, "no such route")),
+    };
+    result.map(Response::ok).unwrap_or_else(|e| e)
+}
+
+#[derive(Deserialize)]
+struct NewTable {
+    seats: u32,
+}
+
+#[derive(Deserialize)]
+struct NewParty {
+    name: String,
+    size: u32,
+}
+
+#[derive(Deserialize)]
+struct Seat {
+    #[serde(default)]
+    table: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct NewOrder {
+    table: u32,
+    items: Vec<String>,
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("state always serializes")
+}
+
+fn body<'a, T: Deserialize<'a>>(request: &'a Request) -> Result<T, Response> {
+    // an empty body reads as `{}` so optional fields can be left out
+    let bytes: &[u8] = if request.body.is_empty() {
+        b"{}"
+    } else {
+        &request.body
+    };
+    serde_json::from_slice(bytes).map_err(|e| Response::error(400, &e.to_string()))
+}
+
+fn id_from(segment: &str) -> Result<u32, Response> {
+    segment
+        .parse()
+        .map_err(|_| Response::error(404, "ids are numbers"))
+}
@@ -1,48 +1,26 @@
 // load contents of the module with same name
 mod front_of_house;
 pub use crate::front_of_house::hosting;
+pub use crate::front_of_house::reservations;
+pub use crate::front_of_house::serving;
 fn serve_order() {}
 
-mod back_of_house {
-    fn fix_incorrect_order() {
-        cook_order();
-        super::serve_order();
-        // since relationship between back_of_house and serve_order
-        // are likely to stay together -> less updates to future code
-    }
-
-    fn cook_order() {}
-
-    /*
-    Structs Public
-    - need to specify public fields
-    - associated methods are needed if any private
-     */
-    pub struct Breakfast {
-        pub toast: String,
-        seasonal_fruit: String, // by default private
-    }
-
-    // Needs to provide a public associated function (instance Breakfast)
-    // since unable to create instance with private field
-    impl Breakfast {
-        pub fn summer(toast: &str) -> Breakfast {
-            Breakfast {
-                toast: String::from(toast),
-                seasonal_fruit: String::from("peaches"),
-            }
-        }
-    }
-    /*
-    Enum Public
-    - if enumal public: all variants are also public
-    wouldn't be useful if private
-     */
-    pub enum Appetizer {
-        Soup,
-        Salad,
-    }
-}
+mod back_of_house;
+pub use crate::back_of_house::{Appetizer, Breakfast, Dish, Menu, MenuItem};
+
+mod error;
+pub use crate::error::Error;
+
+mod state;
+pub use crate::state::Restaurant;
+
+pub mod clock;
+pub mod events;
+pub mod reports;
+pub mod snapshot;
+
+#[cfg(feature = "http")]
+pub mod http;
 
 // Symbolic link (i.e. like filesystem)
 // use crate::front_of_house::hosting;
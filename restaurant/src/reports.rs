@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+use crate::back_of_house::{Appetizer, Breakfast, Dish};
+use crate::events::{Event, EventKind, EventLog};
+use crate::front_of_house::hosting::PartyId;
+use crate::front_of_house::serving::Cents;
+
+const HOUR: u64 = 60 * 60;
+
+/// End-of-day numbers, computed from the event log only
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    // start of the hour (unix seconds) -> guests seated in that hour
+    pub covers_per_hour: BTreeMap<u64, u32>,
+    // seated until check closed, over parties that did both
+    pub average_turn: Option<Duration>,
+    pub average_check_cents: Option<u32>,
+    // most ordered first
+    pub top_appetizers: Vec<(Appetizer, u32)>,
+    pub top_breakfasts: Vec<(Breakfast, u32)>,
+}
+
+impl Report {
+    pub fn from_log(log: &EventLog) -> Report {
+        Report::from_events(log.iter())
+    }
+
+    /// Only counts events in `[start, end)`, i.e. one service or one day
+    pub fn between(log: &EventLog, start: u64, end: u64) -> Report {
+        Report::from_events(log.iter().filter(|e| e.at >= start && e.at < end))
+    }
+
+    pub fn from_events<'a, I: IntoIterator<Item = &'a Event>>(events: I) -> Report {
+        let mut report = Report::default();
+        let mut seated_at: HashMap<PartyId, u64> = HashMap::new();
+        let mut turns: Vec<u64> = Vec::new();
+        let mut checks: Vec<u32> = Vec::new();
+        let mut appetizers: HashMap<Appetizer, u32> = HashMap::new();
+        let mut breakfasts: HashMap<Breakfast, u32> = HashMap::new();
+
+        for e in events {
+            match &e.kind {
+                EventKind::PartySeated { party, size, .. } => {
+                    *report
+                        .covers_per_hour
+                        .entry(e.at - e.at % HOUR)
+                        .or_insert(0) += size;
+                    seated_at.insert(*party, e.at);
+                }
+                EventKind::OrderPlaced { lines, .. } => {
+                    for line in lines {
+                        match &line.dish {
+                            Dish::Appetizer(a) => *appetizers.entry(*a).or_insert(0) += 1,
+                            Dish::Breakfast(b) => *breakfasts.entry(b.clone()).or_insert(0) += 1,
+                        }
+                    }
+                }
+                EventKind::CheckClosed {
+                    party, total_cents, ..
+                } => {
+                    checks.push(*total_cents);
+                    if let Some(seated) = seated_at.remove(party) {
+                        turns.push(e.at.saturating_sub(seated));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        report.average_turn = average(&turns).map(Duration::from_secs);
+        report.average_check_cents =
+            average(&checks.iter().map(|&c| u64::from(c)).collect::<Vec<_>>()).map(|c| c as u32);
+
+        let mut appetizers: Vec<_> = appetizers.into_iter().collect();
+        appetizers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        report.top_appetizers = appetizers;
+
+        let mut breakfasts: Vec<_> = breakfasts.into_iter().collect();
+        breakfasts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.toast.cmp(&b.0.toast)));
+        report.top_breakfasts = breakfasts;
+
+        report
+    }
+
+    pub fn total_covers(&self) -> u32 {
+        self.covers_per_hour.values().sum()
+    }
+}
+
+// rounded to the nearest whole unit
+fn average(values: &[u64]) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as u64;
+    Some((values.iter().sum::<u64>() + n / 2) / n)
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "covers: {}", self.total_covers())?;
+        for (hour, covers) in &self.covers_per_hour {
+            writeln!(f, "  {:02}:00 UTC  {}", hour / HOUR % 24, covers)?;
+        }
+        match self.average_turn {
+            Some(turn) => writeln!(f, "average table turn: {} min", turn.as_secs() / 60)?,
+            None => writeln!(f, "average table turn: -")?,
+        }
+        match self.average_check_cents {
+            Some(cents) => writeln!(f, "average check: {}", Cents(cents))?,
+            None => writeln!(f, "average check: -")?,
+        }
+        writeln!(f, "top appetizers:")?;
+        for (appetizer, count) in &self.top_appetizers {
+            writeln!(f, "  {:?} x{}", appetizer, count)?;
+        }
+        write!(f, "top breakfasts:")?;
+        for (breakfast, count) in &self.top_breakfasts {
+            write!(f, "\n  {} toast x{}", breakfast.toast, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::Restaurant;
+
+    // 2020-06-01 18:00 UTC
+    const SIX_PM: u64 = 1_591_034_400;
+
+    fn service() -> Restaurant {
+        let clock = Arc::new(ManualClock::new(SIX_PM));
+        let mut r = Restaurant::default().with_clock(clock.clone());
+        let t1 = r.add_table(4);
+        let t2 = r.add_table(2);
+
+        let ana = r.add_party("Ana", 3).unwrap();
+        r.seat_party(ana, Some(t1)).unwrap();
+        let o1 = r.place_order(t1, &["soup", "soup", "rye"]).unwrap();
+
+        clock.advance(50 * 60);
+        let bo = r.add_party("Bo", 2).unwrap();
+        r.seat_party(bo, Some(t2)).unwrap();
+        let o2 = r.place_order(t2, &["salad", "wheat", "rye"]).unwrap();
+
+        clock.advance(10 * 60); // 19:00
+        r.serve_order(o1).unwrap();
+        r.close_table(t1).unwrap(); // 60 min turn, 1100 + 900 = 2000
+
+        clock.advance(30 * 60);
+        r.serve_order(o2).unwrap();
+        r.close_table(t2).unwrap(); // 40 min turn, 650 + 900 + 900 = 2450
+        r
+    }
+
+    #[test]
+    fn covers_are_bucketed_by_hour_seated() {
+        let report = Report::from_log(service().events());
+        let hours: Vec<(u64, u32)> = report.covers_per_hour.into_iter().collect();
+        assert_eq!(hours, vec![(SIX_PM, 5)]);
+    }
+
+    #[test]
+    fn averages_turns_and_checks() {
+        let report = Report::from_log(service().events());
+        assert_eq!(report.average_turn, Some(Duration::from_secs(50 * 60)));
+        assert_eq!(report.average_check_cents, Some(2225));
+    }
+
+    #[test]
+    fn ranks_top_sellers() {
+        let report = Report::from_log(service().events());
+        assert_eq!(
+            report.top_appetizers,
+            vec![(Appetizer::Soup, 2), (Appetizer::Salad, 1)]
+        );
+        let breakfasts: Vec<(&str, u32)> = report
+            .top_breakfasts
+            .iter()
+            .map(|(b, n)| (b.toast.as_str(), *n))
+            .collect();
+        assert_eq!(breakfasts, vec![("Rye", 2), ("Wheat", 1)]);
+    }
+
+    #[test]
+    fn between_limits_the_window() {
+        let r = service();
+        let report = Report::between(r.events(), SIX_PM + HOUR, SIX_PM + 2 * HOUR);
+        assert_eq!(report.total_covers(), 0);
+        assert_eq!(report.average_check_cents, Some(2225));
+        // seated before the window, so no turn is known
+        assert_eq!(report.average_turn, None);
+    }
+
+    #[test]
+    fn csv_export() {
+        let csv = service().events().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "at,event,party,table,order,size,items,total_cents,reservation"
+        );
+        assert_eq!(lines[1], format!("{},party_added,1,,,3,,,", SIX_PM));
+        assert_eq!(
+            lines[3],
+            format!("{},order_placed,,1,1,,soup;soup;rye,,", SIX_PM)
+        );
+        assert_eq!(
+            lines[8],
+            format!("{},check_closed,1,1,,,,2000,", SIX_PM + HOUR)
+        );
+        assert_eq!(lines.len(), 1 + 10);
+    }
+}
@@ -0,0 +1,305 @@
+//! On-disk snapshots of a [`Restaurant`](crate::Restaurant)
+//!
+//! A snapshot is a single JSON or TOML document (picked by file extension,
+//! `.toml` is TOML and anything else is JSON). The top level keys are:
+//!
+//! | key             | contents                                                   |
+//! |-----------------|------------------------------------------------------------|
+//! | `version`       | format version, currently [`CURRENT_VERSION`]              |
+//! | `next_party_id` | id handed to the next party added to the waitlist          |
+//! | `next_order_id` | id handed to the next order placed                         |
+//! | `menu`          | list of `{ name, dish, price_cents }`                      |
+//! | `tables`        | list of `{ id, seats, party? }`, `party` is who is seated  |
+//! | `waitlist`      | list of `{ id, name, size }` in seating order              |
+//! | `orders`        | list of `{ id, table, status, lines }`                     |
+//! | `events`        | list of `{ at, event, ... }`, oldest first                 |
+//! | `reservations`  | `{ next_id, policy, bookings }`                            |
+//!
+//! `dish` is externally tagged, i.e. `{"Appetizer": "Soup"}` or
+//! `{"Breakfast": {"toast": "Rye", "seasonal_fruit": "peaches"}}`.
+//! Order `status` is `"Open"` or `"Served"` and each order line is
+//! `{ item, dish, price_cents }` with the price captured when it was ordered.
+//! Each event has `at` (unix seconds), `event` (the
+//! [`EventKind`](crate::events::EventKind) variant name) and that variant's
+//! fields, i.e. `{ "at": 1591034400, "event": "PartySeated", "party": 1,
+//! "table": 1, "size": 2 }`.
+//! `policy` is `{ grace_period, walk_in_turn }` in seconds and each booking is
+//! `{ id, name, size, start, duration, status }` with `start` in unix seconds,
+//! `status` one of `"Booked"`, `{"Arrived": <party id>}`, `"Cancelled"` or
+//! `"NoShow"`.
+//!
+//! ```json
+//! {
+//!   "version": 4,
+//!   "next_party_id": 3,
+//!   "next_order_id": 2,
+//!   "menu": [{ "name": "soup", "dish": { "Appetizer": "Soup" }, "price_cents": 550 }],
+//!   "tables": [{ "id": 1, "seats": 4, "party": { "id": 1, "name": "Ana", "size": 2 } }],
+//!   "waitlist": [{ "id": 2, "name": "Bo", "size": 6 }],
+//!   "orders": [{ "id": 1, "table": 1, "status": "Open", "lines": [
+//!       { "item": "soup", "dish": { "Appetizer": "Soup" }, "price_cents": 550 }
+//!   ] }],
+//!   "events": [{ "at": 1591034400, "event": "PartyAdded", "party": 1, "size": 2 }],
+//!   "reservations": {
+//!     "next_id": 2,
+//!     "policy": { "grace_period": 900, "walk_in_turn": 3600 },
+//!     "bookings": [{ "id": 1, "name": "Cy", "size": 4, "start": 1591038000,
+//!                    "duration": 5400, "status": "Booked" }]
+//!   }
+//! }
+//! ```
+//!
+//! Versions
+//! - 1: tables had no `id` (a table's id was its position in the list,
+//!   starting at 1) and there was no `menu` key, the built-in menu was used
+//! - 2: no `events` key, nothing was logged
+//! - 3: no `reservations` key, nothing could be booked
+//! - 4: current
+//!
+//! Older versions are migrated one step at a time when loaded, saving always
+//! writes [`CURRENT_VERSION`].
+
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::back_of_house::Menu;
+use crate::error::Error;
+use crate::front_of_house::reservations::Reservations;
+use crate::state::Restaurant;
+
+pub const CURRENT_VERSION: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+}
+
+// version goes first so it's the first thing a person reads in the file
+#[derive(Serialize)]
+struct Versioned<'a> {
+    version: u32,
+    #[serde(flatten)]
+    restaurant: &'a Restaurant,
+}
+
+pub fn to_string(restaurant: &Restaurant, format: Format) -> Result<String, Error> {
+    let snapshot = Versioned {
+        version: CURRENT_VERSION,
+        restaurant,
+    };
+    match format {
+        Format::Json => {
+            serde_json::to_string_pretty(&snapshot).map_err(|e| Error::Format(e.to_string()))
+        }
+        Format::Toml => toml::to_string(&snapshot).map_err(|e| Error::Format(e.to_string())),
+    }
+}
+
+pub fn from_str(text: &str, format: Format) -> Result<Restaurant, Error> {
+    let value: Value = match format {
+        Format::Json => serde_json::from_str(text).map_err(|e| Error::Format(e.to_string()))?,
+        Format::Toml => toml::from_str(text).map_err(|e| Error::Format(e.to_string()))?,
+    };
+    let mut value = migrate(value)?;
+    if let Value::Object(map) = &mut value {
+        map.remove("version");
+    }
+    serde_json::from_value(value).map_err(|e| Error::Format(e.to_string()))
+}
+
+/// Writes the snapshot next to `path` first and renames it over, so a crash
+/// mid-write never leaves a half written file behind
+pub fn save(restaurant: &Restaurant, path: &Path) -> Result<(), Error> {
+    let text = to_string(restaurant, Format::from_path(path))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<Restaurant, Error> {
+    let text = fs::read_to_string(path)?;
+    from_str(&text, Format::from_path(path))
+}
+
+/// Brings a parsed snapshot of any known version up to [`CURRENT_VERSION`]
+pub fn migrate(mut value: Value) -> Result<Value, Error> {
+    let map = match &mut value {
+        Value::Object(map) => map,
+        _ => {
+            return Err(Error::Format(String::from(
+                "expected a table at the top level",
+            )))
+        }
+    };
+    let mut version = match map.get("version") {
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| Error::Format(String::from("`version` must be a number")))?,
+        None => return Err(Error::Format(String::from("missing `version`"))),
+    };
+    if version == 0 || version > CURRENT_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    while version < CURRENT_VERSION {
+        match version {
+            1 => v1_to_v2(map)?,
+            2 => v2_to_v3(map),
+            3 => v3_to_v4(map)?,
+            _ => unreachable!("every version below current has a migration"),
+        }
+        version += 1;
+        map.insert(String::from("version"), Value::from(version));
+    }
+    Ok(value)
+}
+
+fn v1_to_v2(map: &mut Map<String, Value>) -> Result<(), Error> {
+    if let Some(Value::Array(tables)) = map.get_mut("tables") {
+        for (idx, table) in tables.iter_mut().enumerate() {
+            match table {
+                Value::Object(t) => {
+                    t.entry("id").or_insert_with(|| Value::from(idx + 1));
+                }
+                _ => return Err(Error::Format(String::from("table must be a table"))),
+            }
+        }
+    }
+    if !map.contains_key("menu") {
+        let menu =
+            serde_json::to_value(Menu::default()).map_err(|e| Error::Format(e.to_string()))?;
+        map.insert(String::from("menu"), menu);
+    }
+    Ok(())
+}
+
+fn v2_to_v3(map: &mut Map<String, Value>) {
+    map.entry("events")
+        .or_insert_with(|| Value::Array(Vec::new()));
+}
+
+fn v3_to_v4(map: &mut Map<String, Value>) -> Result<(), Error> {
+    if !map.contains_key("reservations") {
+        let reservations = serde_json::to_value(Reservations::default())
+            .map_err(|e| Error::Format(e.to_string()))?;
+        map.insert(String::from("reservations"), reservations);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn busy_restaurant() -> Restaurant {
+        let mut r = Restaurant::default();
+        let table = r.add_table(4);
+        r.add_table(2);
+        let ana = r.add_party("Ana", 2).unwrap();
+        r.add_party("Bo", 6).unwrap();
+        r.seat_party(ana, Some(table)).unwrap();
+        let order = r.place_order(table, &["soup", "rye"]).unwrap();
+        r.serve_order(order).unwrap();
+        r.place_order(table, &["salad"]).unwrap();
+        r
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let r = busy_restaurant();
+        let text = to_string(&r, Format::Json).unwrap();
+        assert_eq!(from_str(&text, Format::Json).unwrap(), r);
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let r = busy_restaurant();
+        let text = to_string(&r, Format::Toml).unwrap();
+        assert!(text.starts_with("version = 4"), "{}", text);
+        assert_eq!(from_str(&text, Format::Toml).unwrap(), r);
+    }
+
+    #[test]
+    fn ids_keep_counting_after_reload() {
+        let r = busy_restaurant();
+        let text = to_string(&r, Format::Json).unwrap();
+        let mut reloaded = from_str(&text, Format::Json).unwrap();
+        assert_eq!(reloaded.add_party("Cy", 2).unwrap().0, 3);
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let v1 = r#"{
+            "version": 1,
+            "next_party_id": 2,
+            "next_order_id": 1,
+            "tables": [
+                { "seats": 2 },
+                { "seats": 4, "party": { "id": 1, "name": "Ana", "size": 3 } }
+            ],
+            "waitlist": [],
+            "orders": []
+        }"#;
+        let r = from_str(v1, Format::Json).unwrap();
+
+        let seated = r.tables().table_of(crate::hosting::PartyId(1)).unwrap();
+        assert_eq!(seated.0, 2);
+        assert_eq!(r.menu(), &Menu::default());
+        assert!(r.events().is_empty());
+    }
+
+    #[test]
+    fn migrates_version_2() {
+        let v2 = r#"{
+            "version": 2,
+            "next_party_id": 1,
+            "next_order_id": 1,
+            "menu": [],
+            "tables": [{ "id": 7, "seats": 2 }],
+            "waitlist": [],
+            "orders": []
+        }"#;
+        let r = from_str(v2, Format::Json).unwrap();
+        assert_eq!(r.tables().capacity(), 2);
+        assert!(r.events().is_empty());
+        assert_eq!(r.reservations(), &Reservations::default());
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let text = r#"{ "version": 99 }"#;
+        assert!(matches!(
+            from_str(text, Format::Json),
+            Err(Error::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let dir = std::env::temp_dir().join(format!("restaurant-snapshot-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.toml");
+
+        let r = busy_restaurant();
+        save(&r, &path).unwrap();
+        assert_eq!(load(&path).unwrap(), r);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
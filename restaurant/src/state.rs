@@ -0,0 +1,515 @@
+use serde::{Deserialize, Serialize};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::back_of_house::Menu;
+use crate::clock::{Clock, SharedClock};
+use crate::error::Error;
+use crate::events::{EventKind, EventLog};
+use crate::front_of_house::hosting::{Party, PartyId, TableId, TableMap, Waitlist};
+use crate::front_of_house::reservations::{
+    self, Availability, ReservationId, ReservationPolicy, ReservationStatus, Reservations, Slot,
+};
+use crate::front_of_house::serving::{Check, Order, OrderId, OrderLine, OrderStatus};
+
+/// Everything the front and back of house need to keep between calls:
+/// the floor, the waitlist, bookings, open orders, the menu and what
+/// happened today
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Restaurant {
+    next_party_id: u32,
+    next_order_id: u32,
+    menu: Menu,
+    tables: TableMap,
+    waitlist: Waitlist,
+    orders: Vec<Order>,
+    events: EventLog,
+    reservations: Reservations,
+    #[serde(skip)]
+    clock: SharedClock,
+}
+
+impl Default for Restaurant {
+    fn default() -> Restaurant {
+        Restaurant::new(Menu::default())
+    }
+}
+
+impl Restaurant {
+    pub fn new(menu: Menu) -> Restaurant {
+        Restaurant {
+            next_party_id: 1,
+            next_order_id: 1,
+            menu,
+            tables: TableMap::new(),
+            waitlist: Waitlist::new(),
+            orders: Vec::new(),
+            events: EventLog::new(),
+            reservations: Reservations::default(),
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Stamps events with `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Restaurant {
+        self.clock = SharedClock(clock);
+        self
+    }
+
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    pub fn menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    pub fn menu_mut(&mut self) -> &mut Menu {
+        &mut self.menu
+    }
+
+    pub fn tables(&self) -> &TableMap {
+        &self.tables
+    }
+
+    pub fn waitlist(&self) -> &Waitlist {
+        &self.waitlist
+    }
+
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    pub fn events(&self) -> &EventLog {
+        &self.events
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.iter().filter(|o| o.status == OrderStatus::Open)
+    }
+
+    pub fn add_table(&mut self, seats: u32) -> TableId {
+        self.tables.add_table(seats)
+    }
+
+    /* Hosting */
+
+    pub fn add_party(&mut self, name: &str, size: u32) -> Result<PartyId, Error> {
+        if size == 0 {
+            return Err(Error::InvalidPartySize);
+        }
+        let party = self.new_party(name, size);
+        let id = party.id;
+        self.waitlist.push(party);
+        Ok(id)
+    }
+
+    fn new_party(&mut self, name: &str, size: u32) -> Party {
+        let id = PartyId(self.next_party_id);
+        self.next_party_id += 1;
+        self.record(EventKind::PartyAdded { party: id, size });
+        Party {
+            id,
+            name: String::from(name),
+            size,
+        }
+    }
+
+    /// How long a walk-in of `size` should expect to wait, counting the
+    /// parties already waiting and the tables held for upcoming bookings.
+    /// Seated parties and walk-ins are expected to stay for the policy's
+    /// `walk_in_turn`.
+    pub fn quote_wait(&self, size: u32) -> Result<Duration, Error> {
+        if size == 0 {
+            return Err(Error::InvalidPartySize);
+        }
+        let now = self.now();
+        let turn = self.reservations.policy.walk_in_turn;
+        let tables = self.availability(now);
+        let mut slots = self.booked_slots(now);
+
+        // everyone already waiting gets a table first
+        for waiting in self.waitlist.iter() {
+            if let Some(start) =
+                reservations::earliest_start(&tables, &slots, waiting.size, now, turn)
+            {
+                slots.push(Slot {
+                    size: waiting.size,
+                    start,
+                    end: start.saturating_add(turn),
+                });
+            }
+        }
+        reservations::earliest_start(&tables, &slots, size, now, turn)
+            .map(|start| Duration::from_secs(start - now))
+            .ok_or(Error::NoTableAvailable { size })
+    }
+
+    // when each table is expected to be free again
+    fn availability(&self, now: u64) -> Vec<Availability> {
+        let turn = self.reservations.policy.walk_in_turn;
+        self.tables
+            .iter()
+            .map(|t| Availability {
+                seats: t.seats,
+                free_from: match &t.party {
+                    None => now,
+                    Some(p) => self
+                        .seated_at(p.id)
+                        .map_or(now, |at| at.saturating_add(turn))
+                        .max(now),
+                },
+            })
+            .collect()
+    }
+
+    fn booked_slots(&self, now: u64) -> Vec<Slot> {
+        self.reservations
+            .active(now)
+            .into_iter()
+            .map(|r| Slot {
+                size: r.size,
+                start: r.start.max(now),
+                end: r.end().max(now),
+            })
+            .filter(|s| s.start < s.end)
+            .collect()
+    }
+
+    fn seated_at(&self, party: PartyId) -> Option<u64> {
+        self.events
+            .iter()
+            .filter_map(|e| match e.kind {
+                EventKind::PartySeated { party: p, .. } if p == party => Some(e.at),
+                _ => None,
+            })
+            .last()
+    }
+
+    /* Reservations */
+
+    pub fn reservations(&self) -> &Reservations {
+        &self.reservations
+    }
+
+    pub fn set_reservation_policy(&mut self, policy: ReservationPolicy) {
+        self.reservations.policy = policy;
+    }
+
+    /// Books a table for `size` guests from `start` for `duration` seconds,
+    /// as long as every booking around then can still get its own table
+    pub fn book(
+        &mut self,
+        name: &str,
+        size: u32,
+        start: u64,
+        duration: u64,
+    ) -> Result<ReservationId, Error> {
+        if size == 0 {
+            return Err(Error::InvalidPartySize);
+        }
+        if duration == 0 {
+            return Err(Error::InvalidDuration);
+        }
+        let now = self.now();
+        if start < now {
+            return Err(Error::ReservationInPast);
+        }
+        let end = start
+            .checked_add(duration)
+            .ok_or(Error::InvalidReservation)?;
+        let mut slots = self.booked_slots(now);
+        slots.push(Slot { size, start, end });
+        if !reservations::feasible(&self.availability(now), &slots) {
+            return Err(Error::Overbooked { start });
+        }
+        let id = self.reservations.insert(name, size, start, duration);
+        self.record(EventKind::ReservationBooked {
+            reservation: id,
+            size,
+            start,
+        });
+        Ok(id)
+    }
+
+    pub fn cancel_reservation(&mut self, id: ReservationId) -> Result<(), Error> {
+        let r = self
+            .reservations
+            .get_mut(id)
+            .ok_or(Error::UnknownReservation(id))?;
+        if r.status != ReservationStatus::Booked {
+            return Err(Error::ReservationClosed(id));
+        }
+        r.status = ReservationStatus::Cancelled;
+        self.record(EventKind::ReservationCancelled { reservation: id });
+        Ok(())
+    }
+
+    /// Marks bookings past their grace period as no-shows, freeing the
+    /// tables they were holding
+    pub fn sweep_no_shows(&mut self) -> Vec<ReservationId> {
+        let now = self.now();
+        let policy = self.reservations.policy;
+        let expired: Vec<ReservationId> = self
+            .reservations
+            .iter()
+            .filter(|r| r.status == ReservationStatus::Booked && !r.is_active(now, &policy))
+            .map(|r| r.id)
+            .collect();
+        for &id in &expired {
+            self.reservations.get_mut(id).expect("listed above").status = ReservationStatus::NoShow;
+            self.record(EventKind::ReservationNoShow { reservation: id });
+        }
+        expired
+    }
+
+    /// The booked party has arrived: they go to the front of the waitlist
+    pub fn check_in(&mut self, id: ReservationId) -> Result<PartyId, Error> {
+        self.sweep_no_shows();
+        let r = self
+            .reservations
+            .get(id)
+            .ok_or(Error::UnknownReservation(id))?;
+        match r.status {
+            ReservationStatus::Booked => {}
+            ReservationStatus::NoShow => return Err(Error::ReservationExpired(id)),
+            _ => return Err(Error::ReservationClosed(id)),
+        }
+        let (name, size) = (r.name.clone(), r.size);
+
+        let party = self.new_party(&name, size);
+        let party_id = party.id;
+        self.waitlist.push_front(party);
+        self.reservations.get_mut(id).expect("checked above").status =
+            ReservationStatus::Arrived(party_id);
+        self.record(EventKind::ReservationArrived {
+            reservation: id,
+            party: party_id,
+        });
+        Ok(party_id)
+    }
+
+    /// Seats a waiting party at `table`, or at the smallest free table that
+    /// fits them when no table is given
+    pub fn seat_party(&mut self, party: PartyId, table: Option<TableId>) -> Result<TableId, Error> {
+        let size = self
+            .waitlist
+            .get(party)
+            .ok_or(Error::UnknownParty(party))?
+            .size;
+        let table = match table {
+            Some(id) => {
+                let t = self.tables.get(id).ok_or(Error::UnknownTable(id))?;
+                if !t.is_free() {
+                    return Err(Error::TableOccupied(id));
+                }
+                if t.seats < size {
+                    return Err(Error::TableTooSmall {
+                        table: id,
+                        seats: t.seats,
+                        size,
+                    });
+                }
+                id
+            }
+            None => self
+                .tables
+                .best_fit(size)
+                .ok_or(Error::NoTableAvailable { size })?,
+        };
+        let id = party;
+        let party = self.waitlist.remove(party).expect("party checked above");
+        self.tables
+            .get_mut(table)
+            .expect("table checked above")
+            .party = Some(party);
+        self.record(EventKind::PartySeated {
+            party: id,
+            table,
+            size,
+        });
+        Ok(table)
+    }
+
+    /* Ordering */
+
+    pub fn place_order(&mut self, table: TableId, items: &[&str]) -> Result<OrderId, Error> {
+        let t = self.tables.get(table).ok_or(Error::UnknownTable(table))?;
+        if t.is_free() {
+            return Err(Error::TableNotSeated(table));
+        }
+        if items.is_empty() {
+            return Err(Error::EmptyOrder);
+        }
+        let lines = items
+            .iter()
+            .map(|name| {
+                self.menu
+                    .get(name)
+                    .map(|item| OrderLine {
+                        item: item.name.clone(),
+                        dish: item.dish.clone(),
+                        price_cents: item.price_cents,
+                    })
+                    .ok_or_else(|| Error::UnknownMenuItem(String::from(*name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let id = OrderId(self.next_order_id);
+        self.next_order_id += 1;
+        self.orders.push(Order {
+            id,
+            table,
+            lines: lines.clone(),
+            status: OrderStatus::Open,
+        });
+        self.record(EventKind::OrderPlaced {
+            order: id,
+            table,
+            lines,
+        });
+        Ok(id)
+    }
+
+    pub fn serve_order(&mut self, order: OrderId) -> Result<(), Error> {
+        let o = self
+            .orders
+            .iter_mut()
+            .find(|o| o.id == order)
+            .ok_or(Error::UnknownOrder(order))?;
+        if o.status == OrderStatus::Served {
+            return Err(Error::OrderAlreadyServed(order));
+        }
+        o.status = OrderStatus::Served;
+        let table = o.table;
+        self.record(EventKind::OrderServed { order, table });
+        Ok(())
+    }
+
+    /* Billing */
+
+    /// Running check for a seated table
+    pub fn check(&self, table: TableId) -> Result<Check, Error> {
+        let t = self.tables.get(table).ok_or(Error::UnknownTable(table))?;
+        let party = t.party.as_ref().ok_or(Error::TableNotSeated(table))?;
+        let lines: Vec<OrderLine> = self
+            .orders
+            .iter()
+            .filter(|o| o.table == table)
+            .flat_map(|o| o.lines.iter().cloned())
+            .collect();
+        let total_cents = lines.iter().map(|l| l.price_cents).sum();
+        Ok(Check {
+            table,
+            party: Some(party.name.clone()),
+            lines,
+            total_cents,
+        })
+    }
+
+    /// Takes payment: closes the check, clears the table's orders and frees it
+    pub fn close_table(&mut self, table: TableId) -> Result<Check, Error> {
+        let check = self.check(table)?;
+        if self.open_orders().any(|o| o.table == table) {
+            return Err(Error::OrdersOutstanding(table));
+        }
+        self.orders.retain(|o| o.table != table);
+        let party = self
+            .tables
+            .get_mut(table)
+            .expect("table checked above")
+            .party
+            .take()
+            .expect("check needs a seated party");
+        self.record(EventKind::CheckClosed {
+            party: party.id,
+            table,
+            total_cents: check.total_cents,
+        });
+        Ok(check)
+    }
+
+    fn record(&mut self, kind: EventKind) {
+        let at = self.clock.now();
+        self.events.push(at, kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seats_party_at_smallest_fitting_table() {
+        let mut r = Restaurant::default();
+        let big = r.add_table(6);
+        let small = r.add_table(2);
+        let party = r.add_party("Ana", 2).unwrap();
+
+        assert_eq!(r.seat_party(party, None).unwrap(), small);
+        assert!(r.waitlist().is_empty());
+        assert!(r.tables().get(big).unwrap().is_free());
+    }
+
+    #[test]
+    fn rejects_table_that_is_too_small() {
+        let mut r = Restaurant::default();
+        let table = r.add_table(2);
+        let party = r.add_party("Bo", 5).unwrap();
+
+        match r.seat_party(party, Some(table)) {
+            Err(Error::TableTooSmall {
+                seats: 2, size: 5, ..
+            }) => {}
+            other => panic!("expected TableTooSmall, got {:?}", other),
+        }
+        assert_eq!(r.waitlist().len(), 1);
+    }
+
+    #[test]
+    fn check_totals_every_order_for_the_table() {
+        let mut r = Restaurant::default();
+        let table = r.add_table(4);
+        let party = r.add_party("Cy", 3).unwrap();
+        r.seat_party(party, Some(table)).unwrap();
+        let first = r.place_order(table, &["soup", "rye"]).unwrap();
+        r.place_order(table, &["salad"]).unwrap();
+        r.serve_order(first).unwrap();
+
+        assert_eq!(r.check(table).unwrap().total_cents, 550 + 900 + 650);
+        assert!(matches!(
+            r.close_table(table),
+            Err(Error::OrdersOutstanding(_))
+        ));
+    }
+
+    #[test]
+    fn close_table_frees_it() {
+        let mut r = Restaurant::default();
+        let table = r.add_table(4);
+        let party = r.add_party("Di", 4).unwrap();
+        r.seat_party(party, None).unwrap();
+        let order = r.place_order(table, &["wheat"]).unwrap();
+        r.serve_order(order).unwrap();
+
+        let check = r.close_table(table).unwrap();
+        assert_eq!(check.total_cents, 900);
+        assert!(r.tables().get(table).unwrap().is_free());
+        assert!(r.orders().is_empty());
+    }
+
+    #[test]
+    fn unknown_menu_item_is_an_error() {
+        let mut r = Restaurant::default();
+        let table = r.add_table(2);
+        let party = r.add_party("Ed", 1).unwrap();
+        r.seat_party(party, Some(table)).unwrap();
+
+        assert!(matches!(
+            r.place_order(table, &["soup", "lobster"]),
+            Err(Error::UnknownMenuItem(ref name)) if name == "lobster"
+        ));
+        assert!(r.orders().is_empty());
+    }
+}
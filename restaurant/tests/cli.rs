@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn state_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("restaurant-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn cli(state: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_restaurant-cli"))
+        .arg("--state")
+        .arg(state)
+        .args(args)
+        .env_remove("RESTAURANT_STATE")
+        .output()
+        .unwrap()
+}
+
+fn ok(state: &Path, args: &[&str]) -> String {
+    let out = cli(state, args);
+    assert!(
+        out.status.success(),
+        "{:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn full_service_across_runs() {
+    let dir = state_dir("service");
+    let state = dir.join("state.json");
+
+    ok(&state, &["add-table", "4"]);
+    assert_eq!(
+        ok(&state, &["add-party", "Ana", "3"]).trim(),
+        "added party 1 (0 ahead)"
+    );
+    assert_eq!(
+        ok(&state, &["seat", "1"]).trim(),
+        "seated party 1 at table 1"
+    );
+    assert_eq!(
+        ok(&state, &["order", "1", "soup", "rye"]).trim(),
+        "placed order 1"
+    );
+
+    let status = ok(&state, &["status"]);
+    assert!(status.contains("table 1 (4 seats): Ana x3"), "{}", status);
+    assert!(
+        status.contains("order 1 for table 1: soup, rye"),
+        "{}",
+        status
+    );
+
+    assert!(ok(&state, &["check", "1"]).contains("$14.50"));
+    ok(&state, &["serve", "1"]);
+    let paid = ok(&state, &["pay", "1"]);
+    assert!(paid.ends_with("paid\n"), "{}", paid);
+    assert!(ok(&state, &["status"]).contains("table 1 (4 seats): free"));
+
+    let report = ok(&state, &["report"]);
+    assert!(report.starts_with("covers: 3\n"), "{}", report);
+    assert!(report.contains("average check: $14.50"), "{}", report);
+    let csv = ok(&state, &["events-csv"]);
+    assert_eq!(csv.lines().count(), 1 + 5, "{}", csv);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn domain_errors_exit_with_one_and_keep_state() {
+    let dir = state_dir("errors");
+    let state = dir.join("state.toml");
+
+    ok(&state, &["add-table", "2"]);
+    ok(&state, &["add-party", "Bo", "5"]);
+    let out = cli(&state, &["seat", "1", "1"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("only seats 2"));
+    assert!(ok(&state, &["status"]).contains("party 1: Bo x5"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn bad_usage_exits_with_two() {
+    let dir = state_dir("usage");
+    let state = dir.join("state.json");
+
+    let out = cli(&state, &["seat", "one"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("usage:"));
+    assert!(!state.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,167 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+use restaurant::http::{Server, MAX_HEADER_BYTES};
+use restaurant::Restaurant;
+use serde_json::{json, Value};
+
+fn start() -> SocketAddr {
+    let server = Server::bind_localhost(0, Restaurant::default()).unwrap();
+    let addr = server.local_addr().unwrap();
+    server.spawn();
+    addr
+}
+
+fn call(addr: SocketAddr, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).unwrap();
+    let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn seat_order_serve_and_pay() {
+    let addr = start();
+
+    let (status, table) = call(addr, "POST", "/tables", Some(json!({ "seats": 4 })));
+    assert_eq!((status, table), (200, json!({ "id": 1 })));
+    let (_, party) = call(
+        addr,
+        "POST",
+        "/waitlist",
+        Some(json!({ "name": "Ana", "size": 2 })),
+    );
+    assert_eq!(party, json!({ "id": 1 }));
+    let (status, seated) = call(addr, "POST", "/waitlist/1/seat", None);
+    assert_eq!((status, seated), (200, json!({ "table": 1 })));
+
+    let order = json!({ "table": 1, "items": ["soup", "salad"] });
+    let (status, order) = call(addr, "POST", "/orders", Some(order));
+    assert_eq!((status, order), (200, json!({ "id": 1 })));
+    let (_, open) = call(addr, "GET", "/orders", None);
+    assert_eq!(open.as_array().unwrap().len(), 1);
+
+    let (status, _) = call(addr, "POST", "/tables/1/pay", None);
+    assert_eq!(status, 409, "can't pay before the kitchen is done");
+
+    call(addr, "POST", "/orders/1/serve", None);
+    let (status, check) = call(addr, "POST", "/tables/1/pay", None);
+    assert_eq!(status, 200);
+    assert_eq!(check["total_cents"], json!(1200));
+    assert_eq!(check["party"], json!("Ana"));
+}
+
+#[test]
+fn errors_are_json_with_status() {
+    let addr = start();
+
+    let (status, body) = call(addr, "GET", "/tables/9/check", None);
+    assert_eq!(status, 404);
+    assert_eq!(body, json!({ "error": "no such table 9" }));
+
+    let (status, _) = call(
+        addr,
+        "POST",
+        "/waitlist",
+        Some(json!({ "name": "Bo", "size": 0 })),
+    );
+    assert_eq!(status, 422);
+
+    let (status, _) = call(addr, "POST", "/tables", Some(json!({ "seats": "many" })));
+    assert_eq!(status, 400);
+
+    let (status, _) = call(addr, "DELETE", "/menu", None);
+    assert_eq!(status, 405);
+
+    let (status, _) = call(addr, "GET", "/kitchen", None);
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn header_that_never_ends_is_431() {
+    let addr = start();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    // exactly the allowance and no end of line in sight; not more, or the
+    // server closes with unread data and the response may be reset
+    let start = "GET /menu HTTP/1.1\r\nX-Padding: ";
+    let padding = "a".repeat(MAX_HEADER_BYTES as usize - start.len());
+    write!(stream, "{}{}", start, padding).unwrap();
+
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).unwrap();
+    assert!(
+        raw.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"),
+        "{}",
+        raw
+    );
+    assert!(raw.ends_with(r#"{"error":"request headers are too large"}"#));
+}
+
+#[test]
+fn menu_lists_default_items() {
+    let addr = start();
+    let (status, menu) = call(addr, "GET", "/menu", None);
+    assert_eq!(status, 200);
+    let names: Vec<&str> = menu
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["soup", "salad", "rye", "wheat"]);
+}
+
+#[test]
+fn state_file_follows_every_change() {
+    let dir = std::env::temp_dir().join(format!("restaurant-http-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("state.json");
+
+    let server = Server::bind_localhost(0, Restaurant::default())
+        .unwrap()
+        .with_state_file(path.clone());
+    let addr = server.local_addr().unwrap();
+    server.spawn();
+
+    call(addr, "POST", "/tables", Some(json!({ "seats": 2 })));
+    let saved = restaurant::snapshot::load(&path).unwrap();
+    assert_eq!(saved.tables().capacity(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_requests_are_not_saved() {
+    // nowhere to save to: only a request that changed something finds out
+    let path = std::env::temp_dir()
+        .join(format!("restaurant-http-missing-{}", std::process::id()))
+        .join("state.json");
+    let server = Server::bind_localhost(0, Restaurant::default())
+        .unwrap()
+        .with_state_file(path.clone());
+    let addr = server.local_addr().unwrap();
+    server.spawn();
+
+    let (status, body) = call(addr, "POST", "/orders/9/serve", None);
+    assert_eq!(status, 404);
+    assert_eq!(body, json!({ "error": "no such order 9" }));
+    let (status, _) = call(addr, "POST", "/tables", Some(json!({ "seats": "many" })));
+    assert_eq!(status, 400);
+
+    let (status, _) = call(addr, "POST", "/tables", Some(json!({ "seats": 2 })));
+    assert_eq!(status, 500);
+    assert!(!path.exists());
+}
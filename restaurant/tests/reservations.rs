@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use restaurant::clock::ManualClock;
+use restaurant::reservations::ReservationStatus;
+use restaurant::{Error, Restaurant};
+
+// 2020-06-01 18:00 UTC
+const SIX_PM: u64 = 1_591_034_400;
+const MIN: u64 = 60;
+
+fn restaurant(tables: &[u32]) -> (Restaurant, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new(SIX_PM));
+    let mut r = Restaurant::default().with_clock(clock.clone());
+    for &seats in tables {
+        r.add_table(seats);
+    }
+    (r, clock)
+}
+
+#[test]
+fn rejects_bookings_that_overbook_a_slot() {
+    let (mut r, _) = restaurant(&[4, 2]);
+    r.book("Ana", 4, SIX_PM + 60 * MIN, 90 * MIN).unwrap();
+    r.book("Bo", 2, SIX_PM + 90 * MIN, 30 * MIN).unwrap();
+
+    // both tables are taken at 19:30
+    assert!(matches!(
+        r.book("Cy", 3, SIX_PM + 90 * MIN, 60 * MIN),
+        Err(Error::Overbooked { .. })
+    ));
+    // the 2-top is free again at 20:00 but too small
+    assert!(matches!(
+        r.book("Cy", 3, SIX_PM + 120 * MIN, 30 * MIN),
+        Err(Error::Overbooked { .. })
+    ));
+    // the 4-top is free again at 20:30
+    r.book("Cy", 3, SIX_PM + 150 * MIN, 60 * MIN).unwrap();
+}
+
+#[test]
+fn rejects_bookings_in_the_past() {
+    let (mut r, clock) = restaurant(&[4]);
+    clock.advance(MIN);
+    assert!(matches!(
+        r.book("Ana", 2, SIX_PM, 60 * MIN),
+        Err(Error::ReservationInPast)
+    ));
+}
+
+#[test]
+fn rejects_bookings_that_end_past_u64_max() {
+    let (mut r, _) = restaurant(&[4]);
+    assert!(matches!(
+        r.book("Ana", 2, u64::MAX - 10, 60 * MIN),
+        Err(Error::InvalidReservation)
+    ));
+    assert!(r.reservations().iter().next().is_none());
+
+    // ending exactly at u64::MAX fits, and still blocks the table
+    r.book("Ana", 4, u64::MAX - 60 * MIN, 60 * MIN).unwrap();
+    assert!(matches!(
+        r.book("Bo", 2, u64::MAX - 30 * MIN, 10 * MIN),
+        Err(Error::Overbooked { .. })
+    ));
+    // a walk-in quote near the end doesn't overflow either
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(0));
+}
+
+#[test]
+fn walk_in_quote_works_around_upcoming_bookings() {
+    let (mut r, _) = restaurant(&[4]);
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(0));
+
+    // the only table is held 18:30 - 20:00, too soon for an hour long walk-in
+    r.book("Ana", 4, SIX_PM + 30 * MIN, 90 * MIN).unwrap();
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(120 * MIN));
+}
+
+#[test]
+fn walk_in_quote_counts_seated_and_waiting_parties() {
+    let (mut r, clock) = restaurant(&[4]);
+    let ana = r.add_party("Ana", 4).unwrap();
+    r.seat_party(ana, None).unwrap();
+    clock.advance(20 * MIN);
+
+    // Ana is expected to leave at 19:00
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(40 * MIN));
+
+    // Bo is already waiting, so the next walk-in is after Bo's hour too
+    r.add_party("Bo", 2).unwrap();
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(100 * MIN));
+
+    assert!(matches!(
+        r.quote_wait(9),
+        Err(Error::NoTableAvailable { size: 9 })
+    ));
+}
+
+#[test]
+fn late_bookings_become_no_shows_after_grace_period() {
+    let (mut r, clock) = restaurant(&[4]);
+    let booking = r.book("Ana", 4, SIX_PM + 30 * MIN, 90 * MIN).unwrap();
+
+    clock.set(SIX_PM + 45 * MIN); // still within the 15 minute grace
+    assert!(r.sweep_no_shows().is_empty());
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(75 * MIN));
+
+    clock.advance(1);
+    assert!(matches!(
+        r.check_in(booking),
+        Err(Error::ReservationExpired(id)) if id == booking
+    ));
+    assert_eq!(
+        r.reservations().get(booking).unwrap().status,
+        ReservationStatus::NoShow
+    );
+    // the table is no longer held
+    assert_eq!(r.quote_wait(2).unwrap(), Duration::from_secs(0));
+    assert!(r.events().to_csv().contains("reservation_no_show"));
+}
+
+#[test]
+fn check_in_skips_the_waitlist() {
+    let (mut r, clock) = restaurant(&[4]);
+    let walk_in = r.add_party("Bo", 2).unwrap();
+    let booking = r.book("Ana", 3, SIX_PM + 30 * MIN, 60 * MIN).unwrap();
+
+    clock.set(SIX_PM + 40 * MIN);
+    let party = r.check_in(booking).unwrap();
+    assert_eq!(r.waitlist().position(party), Some(0));
+    assert_eq!(r.waitlist().position(walk_in), Some(1));
+    assert_eq!(
+        r.reservations().get(booking).unwrap().status,
+        ReservationStatus::Arrived(party)
+    );
+    assert!(matches!(
+        r.check_in(booking),
+        Err(Error::ReservationClosed(_))
+    ));
+}
+
+#[test]
+fn cancelling_frees_the_slot() {
+    let (mut r, _) = restaurant(&[2]);
+    let start = SIX_PM + 60 * MIN;
+    let booking = r.book("Ana", 2, start, 60 * MIN).unwrap();
+    assert!(r.book("Bo", 2, start, 60 * MIN).is_err());
+
+    r.cancel_reservation(booking).unwrap();
+    r.book("Bo", 2, start, 60 * MIN).unwrap();
+}
@@ -0,0 +1,133 @@
+// Application errors
+/*
+One error type for the `errors::recoverable` examples to return instead
+of panicking
+
+- Io keeps the io::Error as its source, with the path when there is one
+- NotFound is split out of Io because callers usually treat it
+  differently (create the file, try the next place)
+- Parse keeps whatever the parser returned as its source
+- Validation is for input that parsed but isn't acceptable
+- Display only describes this level; the cause is in source(), so a
+  report walking the chain doesn't say the same thing twice
+ */
+// context chains and reporting, in src/app_error/context.rs
+pub mod context;
+// the errors::recoverable examples, in src/app_error/recoverable.rs
+pub mod recoverable;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum AppError {
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    NotFound(PathBuf),
+    Parse {
+        input: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    Validation(String),
+}
+
+impl AppError {
+    /// An io::Error from working on `path`, NotFound if that's what it was
+    pub fn io<P: AsRef<Path>>(path: P, source: io::Error) -> AppError {
+        let path = path.as_ref().to_path_buf();
+        if source.kind() == io::ErrorKind::NotFound {
+            AppError::NotFound(path)
+        } else {
+            AppError::Io {
+                path: Some(path),
+                source,
+            }
+        }
+    }
+
+    pub fn parse<E>(input: &str, source: E) -> AppError
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        AppError::Parse {
+            input: String::from(input),
+            source: Box::new(source),
+        }
+    }
+
+    pub fn validation(reason: &str) -> AppError {
+        AppError::Validation(String::from(reason))
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Io {
+                path: Some(path), ..
+            } => write!(f, "I/O error on {}", path.display()),
+            AppError::Io { path: None, .. } => write!(f, "I/O error"),
+            AppError::NotFound(path) => write!(f, "{} not found", path.display()),
+            AppError::Parse { input, .. } => write!(f, "could not parse {:?}", input),
+            AppError::Validation(reason) => write!(f, "invalid: {}", reason),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Io { source, .. } => Some(source),
+            AppError::Parse { source, .. } => Some(source.as_ref()),
+            AppError::NotFound(_) | AppError::Validation(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(source: io::Error) -> AppError {
+        AppError::Io { path: None, source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn io_errors_keep_the_path() {
+        let missing = std::env::temp_dir().join("app-error-no-such-file.txt");
+        let e = AppError::io(&missing, File::open(&missing).unwrap_err());
+        assert!(matches!(e, AppError::NotFound(ref p) if *p == missing));
+        assert!(e.source().is_none());
+
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let e = AppError::io("hello.txt", denied);
+        assert_eq!(e.to_string(), "I/O error on hello.txt");
+        assert_eq!(e.source().unwrap().to_string(), "denied");
+
+        // ? on an io::Error, no path to add
+        let e: AppError = io::Error::other("disk on fire").into();
+        assert_eq!(e.to_string(), "I/O error");
+        assert_eq!(e.source().unwrap().to_string(), "disk on fire");
+    }
+
+    #[test]
+    fn parse_and_validation() {
+        let e = AppError::parse("4x", "4x".parse::<u32>().unwrap_err());
+        assert_eq!(e.to_string(), "could not parse \"4x\"");
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "invalid digit found in string"
+        );
+
+        let e = AppError::validation("guess must be between 1 and 100");
+        assert_eq!(e.to_string(), "invalid: guess must be between 1 and 100");
+        assert!(e.source().is_none());
+    }
+}
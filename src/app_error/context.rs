@@ -0,0 +1,285 @@
+// Error context
+/*
+`?` in `errors::recoverable::propagating` passes an io::Error up as it
+is, so the caller sees "No such file or directory" without knowing which
+file or what was being done
+
+- `.context("reading username")` on a Result wraps the error in a
+  ContextError whose source() is the original, so each layer adds a line
+  and the chain keeps every cause; on an Option, None becomes an error
+  with no cause
+- with_context takes a closure, for messages that format something and
+  shouldn't be built unless there is an error
+- the innermost ContextError captures a Backtrace, which std only fills
+  in when RUST_BACKTRACE or RUST_LIB_BACKTRACE is set
+- Report prints the error, then every cause numbered, then the backtrace
+  if asked for and there is one:
+
+      reading username from hello.txt
+
+      Caused by:
+          0: I/O error on hello.txt
+          1: permission denied
+ */
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use std::fmt;
+
+pub struct ContextError {
+    context: String,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+    // only on the innermost one
+    backtrace: Option<Backtrace>,
+}
+
+impl ContextError {
+    pub fn new<C: fmt::Display>(context: C) -> ContextError {
+        ContextError {
+            context: context.to_string(),
+            source: None,
+            backtrace: Some(Backtrace::capture()),
+        }
+    }
+
+    fn wrap<C, E>(context: C, error: E) -> ContextError
+    where
+        C: fmt::Display,
+        E: Error + Send + Sync + 'static,
+    {
+        let inner_has_one = (&error as &(dyn Error + 'static))
+            .downcast_ref::<ContextError>()
+            .is_some_and(|inner| inner.backtrace().is_some());
+        ContextError {
+            context: context.to_string(),
+            source: Some(Box::new(error)),
+            backtrace: if inner_has_one {
+                None
+            } else {
+                Some(Backtrace::capture())
+            },
+        }
+    }
+
+    /// Captures a backtrace whatever the environment says, replacing
+    /// this level's
+    pub fn force_backtrace(mut self) -> ContextError {
+        self.backtrace = Some(Backtrace::force_capture());
+        self
+    }
+
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// The backtrace from the innermost ContextError, if one was captured
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        let inner = self
+            .source
+            .as_deref()
+            .and_then(|s| s.downcast_ref::<ContextError>())
+            .and_then(ContextError::backtrace);
+        let own = self
+            .backtrace
+            .as_ref()
+            .filter(|bt| bt.status() == BacktraceStatus::Captured);
+        inner.or(own)
+    }
+}
+
+impl fmt::Debug for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Report::new(self))
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl Error for ContextError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
+}
+
+pub trait Context<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T, ContextError>;
+
+    fn with_context<C, F>(self, f: F) -> Result<T, ContextError>
+    where
+        C: fmt::Display,
+        F: FnOnce() -> C;
+}
+
+impl<T, E: Error + Send + Sync + 'static> Context<T> for Result<T, E> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T, ContextError> {
+        self.map_err(|error| ContextError::wrap(context, error))
+    }
+
+    fn with_context<C, F>(self, f: F) -> Result<T, ContextError>
+    where
+        C: fmt::Display,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|error| ContextError::wrap(f(), error))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T, ContextError> {
+        self.ok_or_else(|| ContextError::new(context))
+    }
+
+    fn with_context<C, F>(self, f: F) -> Result<T, ContextError>
+    where
+        C: fmt::Display,
+        F: FnOnce() -> C,
+    {
+        self.ok_or_else(|| ContextError::new(f()))
+    }
+}
+
+/// Any error with its whole cause chain, see the notes at the top
+pub struct Report<'a> {
+    error: &'a (dyn Error + 'static),
+    backtrace: bool,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(error: &'a (dyn Error + 'static)) -> Report<'a> {
+        Report {
+            error,
+            backtrace: false,
+        }
+    }
+
+    pub fn with_backtrace(mut self, backtrace: bool) -> Report<'a> {
+        self.backtrace = backtrace;
+        self
+    }
+
+    fn causes(&self) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+        std::iter::successors(self.error.source(), |&e| e.source())
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        let mut causes = self.causes().peekable();
+        if causes.peek().is_some() {
+            write!(f, "\n\nCaused by:")?;
+            for (i, cause) in causes.enumerate() {
+                write!(f, "\n    {}: {}", i, cause)?;
+            }
+        }
+        if self.backtrace {
+            let backtrace = self
+                .error
+                .downcast_ref::<ContextError>()
+                .and_then(ContextError::backtrace);
+            if let Some(backtrace) = backtrace {
+                write!(f, "\n\nStack backtrace:\n{}", backtrace)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_error::AppError;
+    use crate::test_util::TempDir;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    // like errors::recoverable::propagating, with context on each step
+    fn read_username_from_file(path: &Path) -> Result<String, ContextError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| AppError::io(path, e))
+            .with_context(|| format!("reading username from {}", path.display()))?;
+        let name = text
+            .lines()
+            .next()
+            .context("username file is empty")
+            .context("parsing the username file")?;
+        Ok(String::from(name.trim()))
+    }
+
+    #[test]
+    fn renders_the_chain() {
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "permission denied");
+        let error = Err::<(), _>(AppError::io("hello.txt", denied))
+            .context("reading username from hello.txt")
+            .context("loading settings")
+            .unwrap_err();
+        assert_eq!(
+            Report::new(&error).to_string(),
+            "loading settings\n\
+             \n\
+             Caused by:\n    \
+             0: reading username from hello.txt\n    \
+             1: I/O error on hello.txt\n    \
+             2: permission denied"
+        );
+        assert_eq!(error.to_string(), "loading settings");
+        assert_eq!(error.context(), "loading settings");
+    }
+
+    #[test]
+    fn reports_which_file_and_step() {
+        let dir = TempDir::new("context");
+
+        let missing = dir.join("missing.txt");
+        let error = read_username_from_file(&missing).unwrap_err();
+        assert_eq!(
+            Report::new(&error).to_string(),
+            format!(
+                "reading username from {0}\n\nCaused by:\n    0: {0} not found",
+                missing.display()
+            )
+        );
+
+        let empty = dir.join("empty.txt");
+        fs::write(&empty, "").unwrap();
+        let error = read_username_from_file(&empty).unwrap_err();
+        assert_eq!(
+            Report::new(&error).to_string(),
+            "parsing the username file\n\nCaused by:\n    0: username file is empty"
+        );
+
+        let good = dir.join("good.txt");
+        fs::write(&good, "ferris\n").unwrap();
+        assert_eq!(read_username_from_file(&good).unwrap(), "ferris");
+    }
+
+    #[test]
+    fn backtrace_only_when_asked_and_captured() {
+        let plain = Err::<(), _>(io::Error::other("boom"))
+            .context("outer")
+            .unwrap_err();
+        let with = Report::new(&plain).with_backtrace(true).to_string();
+        // std leaves it empty unless RUST_BACKTRACE is set
+        assert_eq!(
+            with.contains("Stack backtrace:"),
+            plain.backtrace().is_some()
+        );
+
+        let forced = Err::<(), _>(ContextError::new("inner").force_backtrace())
+            .context("outer")
+            .unwrap_err();
+        assert!(forced.backtrace().is_some());
+        let report = Report::new(&forced).with_backtrace(true).to_string();
+        assert!(report.starts_with("outer\n\nCaused by:\n    0: inner\n\nStack backtrace:\n"));
+        assert!(!Report::new(&forced)
+            .to_string()
+            .contains("Stack backtrace:"));
+    }
+}
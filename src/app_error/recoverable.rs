@@ -0,0 +1,244 @@
+// Recoverable errors
+/*
+The `errors::recoverable` examples from src/errors.rs, in the library so
+they're compiled and tested (src/errors.rs is one of the commented out
+chapters in src/main.rs)
+
+- every example hands its error back as an AppError instead of panicking
+- they take the path instead of always opening hello.txt in the current
+  dir, so the tests can use a temp dir
+ */
+use super::AppError;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+pub fn open_file(path: &Path) -> Result<File, AppError> {
+    let f = File::open(path);
+
+    let f = match f {
+        Ok(file) => file,
+        // was: panic!("Problem opening the file: {:?}", error)
+        Err(error) => return Err(AppError::io(path, error)),
+    };
+    Ok(f)
+}
+
+// users::source::UsernameSource does this for real: a search path,
+// and a policy for when the file is missing
+pub fn handling(path: &Path) -> Result<File, AppError> {
+    let f = File::open(path);
+
+    let f = match f {
+        Ok(file) => file,
+        Err(error) => match error.kind() {
+            ErrorKind::NotFound => match File::create(path) {
+                Ok(fc) => fc,
+                Err(e) => return Err(AppError::io(path, e)),
+            },
+            _ => return Err(AppError::io(path, error)),
+        },
+    };
+    Ok(f)
+}
+
+// same as above, no (primitive) match statements, easier to read
+pub fn handling_closures(path: &Path) -> Result<File, AppError> {
+    File::open(path)
+        .or_else(|error| {
+            if error.kind() == ErrorKind::NotFound {
+                File::create(path)
+            } else {
+                Err(error)
+            }
+        })
+        .map_err(|error| AppError::io(path, error))
+}
+
+// shortcuts for panic! `unwrap` and `expect`
+// `?` is the shortcut that doesn't panic: From<io::Error> turns the
+// error into an AppError and returns it, without the path
+pub fn unwrap_example(path: &Path) -> Result<File, AppError> {
+    let f = File::open(path)?;
+    Ok(f)
+}
+
+// where `expect` would add a message and panic, map_err adds the path to
+// the error and `?` returns it
+pub fn expect_example(path: &Path) -> Result<File, AppError> {
+    let f = File::open(path).map_err(|e| AppError::io(path, e))?;
+    Ok(f)
+}
+
+// each step says what it was doing, so the caller sees "reading username
+// from hello.txt" above the io::Error instead of just "No such file or
+// directory"; Report::new(&error) prints the whole chain
+pub mod propagating {
+    use crate::app_error::context::{Context, ContextError};
+    use crate::app_error::AppError;
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::path::Path;
+
+    // the long way, what ? does below
+    #[allow(clippy::question_mark)]
+    pub fn read_username_from_file(path: &Path) -> Result<String, ContextError> {
+        let f = File::open(path).map_err(|e| AppError::io(path, e));
+
+        let mut f = match f.context("opening the username file") {
+            Ok(file) => file,
+            Err(e) => return Err(e),
+        };
+
+        let mut s = String::new();
+
+        // returns an error that also might fail
+        match f
+            .read_to_string(&mut s)
+            .map_err(|e| AppError::io(path, e))
+            .with_context(|| format!("reading username from {}", path.display()))
+        {
+            Ok(_) => Ok(s),
+            Err(e) => Err(e),
+        }
+    }
+
+    // using the ? Operator with the same functionality
+    pub fn read_username_from_file_operator(path: &Path) -> Result<String, ContextError> {
+        let mut f = File::open(path)
+            .map_err(|e| AppError::io(path, e))
+            .context("opening the username file")?;
+        let mut s = String::new();
+        f.read_to_string(&mut s)
+            .map_err(|e| AppError::io(path, e))
+            .with_context(|| format!("reading username from {}", path.display()))?;
+        Ok(s)
+    }
+
+    // one context for both steps
+    pub fn shorter(path: &Path) -> Result<String, ContextError> {
+        let mut s = String::new();
+
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut s))
+            .map_err(|e| AppError::io(path, e))
+            .with_context(|| format!("reading username from {}", path.display()))?;
+
+        Ok(s)
+    }
+
+    pub fn shortest(path: &Path) -> Result<String, ContextError> {
+        // opens the file, creates new string, reads the file, put content into
+        // string, and returns it
+        fs::read_to_string(path)
+            .map_err(|e| AppError::io(path, e))
+            .with_context(|| format!("reading username from {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::error::Error;
+    use std::fs;
+
+    #[test]
+    fn missing_file_is_not_found_with_the_path() {
+        let dir = TempDir::new("recoverable-missing");
+        let hello = dir.join("hello.txt");
+        for open in &[open_file, expect_example] {
+            match open(&hello) {
+                Err(AppError::NotFound(path)) => assert_eq!(path, hello),
+                other => panic!("{:?}", other),
+            }
+        }
+        // ? alone has no path to add
+        let e = unwrap_example(&hello).unwrap_err();
+        assert!(matches!(e, AppError::Io { path: None, .. }));
+        assert_eq!(e.to_string(), "I/O error");
+    }
+
+    #[test]
+    fn other_errors_are_io_with_the_path() {
+        let dir = TempDir::new("recoverable-io");
+        // a file where a directory should be: not NotFound, so nothing is
+        // created
+        let file = dir.join("file.txt");
+        fs::write(&file, "").unwrap();
+        let under = file.join("hello.txt");
+        let e = handling(&under).unwrap_err();
+        assert!(matches!(e, AppError::Io { path: Some(ref p), .. } if *p == under));
+        assert_eq!(e.to_string(), format!("I/O error on {}", under.display()));
+        assert!(e.source().is_some());
+
+        let inside = dir.join("no-such-dir").join("hello.txt");
+        let e = handling_closures(&inside).unwrap_err();
+        assert_eq!(e.to_string(), format!("{} not found", inside.display()));
+    }
+
+    #[test]
+    fn propagating_reports_the_file_and_step() {
+        use super::propagating::*;
+        use crate::app_error::context::Report;
+
+        let dir = TempDir::new("recoverable-propagating");
+        let missing = dir.join("hello.txt");
+        let not_found = format!("{} not found", missing.display());
+        for read in &[read_username_from_file, read_username_from_file_operator] {
+            assert_eq!(
+                Report::new(&read(&missing).unwrap_err()).to_string(),
+                format!(
+                    "opening the username file\n\nCaused by:\n    0: {}",
+                    not_found
+                )
+            );
+        }
+        for read in &[shorter, shortest] {
+            assert_eq!(
+                Report::new(&read(&missing).unwrap_err()).to_string(),
+                format!(
+                    "reading username from {}\n\nCaused by:\n    0: {}",
+                    missing.display(),
+                    not_found
+                )
+            );
+        }
+
+        // opening a directory works, reading it doesn't
+        let is_dir = fs::read_to_string(dir.path()).unwrap_err().to_string();
+        let e = read_username_from_file_operator(dir.path()).unwrap_err();
+        assert_eq!(
+            Report::new(&e).to_string(),
+            format!(
+                "reading username from {0}\n\nCaused by:\n    0: I/O error on {0}\n    1: {1}",
+                dir.path().display(),
+                is_dir
+            )
+        );
+
+        fs::write(&missing, "ferris").unwrap();
+        for read in &[
+            read_username_from_file,
+            read_username_from_file_operator,
+            shorter,
+            shortest,
+        ] {
+            assert_eq!(read(&missing).unwrap(), "ferris");
+        }
+    }
+
+    #[test]
+    fn handling_creates_the_file() {
+        let dir = TempDir::new("recoverable-create");
+        let hello = dir.join("hello.txt");
+        handling(&hello).unwrap();
+        assert!(hello.exists());
+        open_file(&hello).unwrap();
+
+        let other = dir.join("other.txt");
+        handling_closures(&other).unwrap();
+        unwrap_example(&other).unwrap();
+        expect_example(&other).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+// Checked arithmetic
+/*
+`plus_one` in `enums::option_example` matches on the Option by hand, and
+`enums::null_example` can't add an i8 to an Option<i8>; these do that for
+every integer type
+
+- CheckedInt is implemented for all the integer types, `None` instead of
+  overflowing or dividing by zero
+- CheckedOption adds add/sub/mul/div to Option<T>: a None on either side
+  or an overflow is None, and the right side can be a plain T too,
+  so `Some(5).checked_add(1)` is plus_one
+- CheckedResult does the same for Result<T, E>: the first Err is kept, and
+  overflow or division by zero becomes an ArithError turned into E
+- zip_with, zip_with3, try_zip_with, lift2 and lift3 combine Options (or
+  Results) with any function, not just arithmetic
+ */
+use std::error::Error;
+use std::fmt;
+
+/// Integer types with checked arithmetic
+pub trait CheckedInt: Copy + Sized {
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+    fn checked_neg(self) -> Option<Self>;
+    fn is_zero(&self) -> bool;
+}
+
+// the inherent methods of the same name do the work
+macro_rules! checked_int {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedInt for $t {
+                fn checked_add(self, other: $t) -> Option<$t> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: $t) -> Option<$t> {
+                    <$t>::checked_sub(self, other)
+                }
+
+                fn checked_mul(self, other: $t) -> Option<$t> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn checked_div(self, other: $t) -> Option<$t> {
+                    <$t>::checked_div(self, other)
+                }
+
+                fn checked_neg(self) -> Option<$t> {
+                    <$t>::checked_neg(self)
+                }
+
+                fn is_zero(&self) -> bool {
+                    *self == 0
+                }
+            }
+        )*
+    };
+}
+
+checked_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithError {
+    Overflow,
+    DivisionByZero,
+}
+
+impl fmt::Display for ArithError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArithError::Overflow => write!(f, "arithmetic overflow"),
+            ArithError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl Error for ArithError {}
+
+pub trait CheckedOption<T> {
+    fn checked_add(self, rhs: impl Into<Option<T>>) -> Option<T>;
+    fn checked_sub(self, rhs: impl Into<Option<T>>) -> Option<T>;
+    fn checked_mul(self, rhs: impl Into<Option<T>>) -> Option<T>;
+    fn checked_div(self, rhs: impl Into<Option<T>>) -> Option<T>;
+}
+
+impl<T: CheckedInt> CheckedOption<T> for Option<T> {
+    fn checked_add(self, rhs: impl Into<Option<T>>) -> Option<T> {
+        self?.checked_add(rhs.into()?)
+    }
+
+    fn checked_sub(self, rhs: impl Into<Option<T>>) -> Option<T> {
+        self?.checked_sub(rhs.into()?)
+    }
+
+    fn checked_mul(self, rhs: impl Into<Option<T>>) -> Option<T> {
+        self?.checked_mul(rhs.into()?)
+    }
+
+    fn checked_div(self, rhs: impl Into<Option<T>>) -> Option<T> {
+        self?.checked_div(rhs.into()?)
+    }
+}
+
+pub trait CheckedResult<T, E> {
+    fn checked_add(self, rhs: Result<T, E>) -> Result<T, E>;
+    fn checked_sub(self, rhs: Result<T, E>) -> Result<T, E>;
+    fn checked_mul(self, rhs: Result<T, E>) -> Result<T, E>;
+    fn checked_div(self, rhs: Result<T, E>) -> Result<T, E>;
+}
+
+impl<T: CheckedInt, E: From<ArithError>> CheckedResult<T, E> for Result<T, E> {
+    fn checked_add(self, rhs: Result<T, E>) -> Result<T, E> {
+        Ok(self?.checked_add(rhs?).ok_or(ArithError::Overflow)?)
+    }
+
+    fn checked_sub(self, rhs: Result<T, E>) -> Result<T, E> {
+        Ok(self?.checked_sub(rhs?).ok_or(ArithError::Overflow)?)
+    }
+
+    fn checked_mul(self, rhs: Result<T, E>) -> Result<T, E> {
+        Ok(self?.checked_mul(rhs?).ok_or(ArithError::Overflow)?)
+    }
+
+    fn checked_div(self, rhs: Result<T, E>) -> Result<T, E> {
+        let (lhs, rhs) = (self?, rhs?);
+        if rhs.is_zero() {
+            return Err(ArithError::DivisionByZero.into());
+        }
+        // MIN / -1
+        Ok(lhs.checked_div(rhs).ok_or(ArithError::Overflow)?)
+    }
+}
+
+/// `f` applied to both values, `None` if either is
+pub fn zip_with<A, B, C, F>(a: Option<A>, b: Option<B>, f: F) -> Option<C>
+where
+    F: FnOnce(A, B) -> C,
+{
+    Some(f(a?, b?))
+}
+
+pub fn zip_with3<A, B, C, D, F>(a: Option<A>, b: Option<B>, c: Option<C>, f: F) -> Option<D>
+where
+    F: FnOnce(A, B, C) -> D,
+{
+    Some(f(a?, b?, c?))
+}
+
+/// `f` applied to both values, or the first Err
+pub fn try_zip_with<A, B, C, E, F>(a: Result<A, E>, b: Result<B, E>, f: F) -> Result<C, E>
+where
+    F: FnOnce(A, B) -> C,
+{
+    Ok(f(a?, b?))
+}
+
+/// A function on values turned into one on Options
+pub fn lift2<A, B, C, F>(f: F) -> impl Fn(Option<A>, Option<B>) -> Option<C>
+where
+    F: Fn(A, B) -> C,
+{
+    move |a, b| zip_with(a, b, &f)
+}
+
+pub fn lift3<A, B, C, D, F>(f: F) -> impl Fn(Option<A>, Option<B>, Option<C>) -> Option<D>
+where
+    F: Fn(A, B, C) -> D,
+{
+    move |a, b, c| zip_with3(a, b, c, &f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn option_operands() {
+        // plus_one and the i8 + Option<i8> from enums.rs
+        assert_eq!(Some(5).checked_add(1), Some(6));
+        assert_eq!(None::<i32>.checked_add(1), None);
+        let (x, y): (i8, Option<i8>) = (5, Some(5));
+        assert_eq!(y.checked_add(x), Some(10));
+
+        assert_eq!(Some(i8::MAX).checked_add(1), None);
+        assert_eq!(Some(0u8).checked_sub(1), None);
+        assert_eq!(Some(7u64).checked_mul(Some(6)), Some(42));
+        assert_eq!(Some(7).checked_div(0), None);
+        assert_eq!(Some(i32::MIN).checked_div(-1), None);
+        assert_eq!(Some(7usize).checked_div(None), None);
+        // chains stop at the first None
+        assert_eq!(
+            Some(100u8).checked_mul(2).checked_add(100).checked_sub(50),
+            None
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum CalcError {
+        Arith(ArithError),
+        Parse,
+    }
+
+    impl From<ArithError> for CalcError {
+        fn from(e: ArithError) -> CalcError {
+            CalcError::Arith(e)
+        }
+    }
+
+    fn parse(s: &str) -> Result<i16, CalcError> {
+        s.parse().map_err(|_| CalcError::Parse)
+    }
+
+    #[test]
+    fn result_operands() {
+        assert_eq!(parse("40").checked_add(parse("2")), Ok(42));
+        assert_eq!(parse("x").checked_add(parse("2")), Err(CalcError::Parse));
+        assert_eq!(
+            parse("30000").checked_mul(parse("2")),
+            Err(CalcError::Arith(ArithError::Overflow))
+        );
+        assert_eq!(
+            parse("1").checked_div(parse("0")),
+            Err(CalcError::Arith(ArithError::DivisionByZero))
+        );
+        assert_eq!(
+            parse("-32768").checked_div(parse("-1")),
+            Err(CalcError::Arith(ArithError::Overflow))
+        );
+        let plain: Result<u32, ArithError> = Ok(3);
+        assert_eq!(plain.checked_sub(Ok(4)), Err(ArithError::Overflow));
+    }
+
+    #[test]
+    fn zips_and_lifts() {
+        assert_eq!(
+            zip_with(Some(2), Some("ab"), |n, s| s.repeat(n)),
+            Some(String::from("abab"))
+        );
+        assert_eq!(zip_with(Some(2), None::<i32>, |a, b| a + b), None);
+        assert_eq!(
+            zip_with3(Some(1), Some(2), Some(3), |a, b, c| a * b * c),
+            Some(6)
+        );
+        assert_eq!(try_zip_with(Ok::<_, ()>(2), Ok(3), |a, b| a - b), Ok(-1));
+        assert_eq!(
+            try_zip_with(Err("first"), Err("second"), |a: i32, b: i32| a + b),
+            Err("first")
+        );
+
+        let max = lift2(std::cmp::max);
+        assert_eq!(max(Some(3), Some(9)), Some(9));
+        assert_eq!(max(Some(3), None), None);
+        let clamp = lift3(|v: i32, lo, hi| v.clamp(lo, hi));
+        assert_eq!(clamp(Some(15), Some(0), Some(10)), Some(10));
+    }
+
+    proptest! {
+        // agrees with doing it in a wider type and checking the range
+        #[test]
+        fn matches_wide_arithmetic(a in any::<Option<i8>>(), b in any::<Option<i8>>()) {
+            let wide = |f: fn(i32, i32) -> Option<i32>| {
+                zip_with(a, b, |a, b| f(i32::from(a), i32::from(b)))
+                    .flatten()
+                    .filter(|v| i8::try_from(*v).is_ok())
+                    .map(|v| v as i8)
+            };
+            prop_assert_eq!(a.checked_add(b), wide(|a, b| Some(a + b)));
+            prop_assert_eq!(a.checked_sub(b), wide(|a, b| Some(a - b)));
+            prop_assert_eq!(a.checked_mul(b), wide(|a, b| Some(a * b)));
+            prop_assert_eq!(a.checked_div(b), wide(|a, b| a.checked_div(b)));
+        }
+    }
+}
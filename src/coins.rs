@@ -0,0 +1,441 @@
+// Coins
+/*
+The Coin enum from `enums::value_in_cents_example`, with a purse of coins
+and change making
+
+- a CoinPurse counts how many of each coin it holds
+- make_change finds the fewest coins for an amount using only the coins
+  in a purse; greedy (biggest coin first) isn't enough once coins run
+  out: 30 cents from a quarter and three dimes is three dimes, greedy takes
+  the quarter and gets stuck
+- the search only tries a few counts of each coin near the most that fit,
+  so it takes the same time for 30 cents as for u32::MAX:
+    - if the coins under a quarter number 25 or more, some of them add up
+      to 25m cents (prefix sums mod 25) using more than m coins, so
+      swapping in m <= 10 quarters would be better; the fewest coins use
+      within 9 quarters of the most that fit or of all the quarters there
+      are
+    - the same with 10 coins under a dime and m <= 5 dimes: within 4
+    - 5 pennies are always worse than a nickel, so it's as many nickels as
+      fit, and pennies for the rest
+ */
+// the state quarters and collecting them, in src/coins/quarters.rs
+pub mod quarters;
+// vending machine, in src/coins/vending.rs
+pub mod vending;
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Coin {
+    Penny,
+    Nickel,
+    Dime,
+    Quarter,
+}
+
+impl Coin {
+    pub const ALL: [Coin; 4] = [Coin::Penny, Coin::Nickel, Coin::Dime, Coin::Quarter];
+
+    pub fn value_in_cents(&self) -> u32 {
+        match self {
+            Coin::Penny => 1,
+            Coin::Nickel => 5,
+            Coin::Dime => 10,
+            Coin::Quarter => 25,
+        }
+    }
+}
+
+impl fmt::Display for Coin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Coin::Penny => "penny",
+            Coin::Nickel => "nickel",
+            Coin::Dime => "dime",
+            Coin::Quarter => "quarter",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeError {
+    // no combination of the coins adds up to it
+    ExactChangeImpossible(u32),
+    // taking more of a coin than the purse has
+    NotEnough { coin: Coin, have: u32, wanted: u32 },
+    // adding would take the count past u32::MAX
+    TooMany { coin: Coin, have: u32, adding: u32 },
+}
+
+impl fmt::Display for ChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChangeError::ExactChangeImpossible(cents) => {
+                write!(f, "can't make exact change for {} cents", cents)
+            }
+            ChangeError::NotEnough { coin, have, wanted } => {
+                write!(f, "wanted {} of {}, only have {}", wanted, coin, have)
+            }
+            ChangeError::TooMany { coin, have, adding } => {
+                write!(f, "can't add {} of {} to {}", adding, coin, have)
+            }
+        }
+    }
+}
+
+impl Error for ChangeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CoinPurse {
+    // indexed by `coin as usize`, in Coin::ALL order
+    counts: [u32; 4],
+}
+
+impl CoinPurse {
+    pub fn new() -> CoinPurse {
+        CoinPurse::default()
+    }
+
+    /// A purse from (coin, count) pairs
+    ///
+    /// Panics if the counts for one coin add up past u32::MAX
+    pub fn of(coins: &[(Coin, u32)]) -> CoinPurse {
+        let mut purse = CoinPurse::new();
+        for &(coin, n) in coins {
+            purse.add(coin, n).expect("counts fit in a u32");
+        }
+        purse
+    }
+
+    pub fn count(&self, coin: Coin) -> u32 {
+        self.counts[coin as usize]
+    }
+
+    /// Adds `n` of `coin`, or nothing if the count would go past u32::MAX
+    pub fn add(&mut self, coin: Coin, n: u32) -> Result<(), ChangeError> {
+        let have = self.count(coin);
+        let total = have.checked_add(n).ok_or(ChangeError::TooMany {
+            coin,
+            have,
+            adding: n,
+        })?;
+        self.counts[coin as usize] = total;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, coin: Coin, n: u32) -> Result<(), ChangeError> {
+        let have = self.count(coin);
+        if have < n {
+            return Err(ChangeError::NotEnough {
+                coin,
+                have,
+                wanted: n,
+            });
+        }
+        self.counts[coin as usize] -= n;
+        Ok(())
+    }
+
+    /// Adds every coin in `other`, or nothing if a count would overflow
+    pub fn merge(&mut self, other: &CoinPurse) -> Result<(), ChangeError> {
+        let mut merged = *self;
+        for (coin, n) in other.iter() {
+            merged.add(coin, n)?;
+        }
+        *self = merged;
+        Ok(())
+    }
+
+    /// Removes every coin in `other`, or nothing if one runs short
+    pub fn take(&mut self, other: &CoinPurse) -> Result<(), ChangeError> {
+        if let Some((coin, wanted)) = other.iter().find(|&(c, n)| self.count(c) < n) {
+            return Err(ChangeError::NotEnough {
+                coin,
+                have: self.count(coin),
+                wanted,
+            });
+        }
+        for (coin, n) in other.iter() {
+            self.counts[coin as usize] -= n;
+        }
+        Ok(())
+    }
+
+    // u64: u32::MAX quarters is more than u32::MAX cents
+    pub fn total_cents(&self) -> u64 {
+        self.iter().map(|(c, n)| value_of(c, n)).sum()
+    }
+
+    pub fn coin_count(&self) -> u64 {
+        self.counts.iter().map(|&n| u64::from(n)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coin_count() == 0
+    }
+
+    /// The coins it holds and how many, smallest coin first
+    pub fn iter(&self) -> impl Iterator<Item = (Coin, u32)> + '_ {
+        Coin::ALL
+            .iter()
+            .map(move |&c| (c, self.count(c)))
+            .filter(|&(_, n)| n > 0)
+    }
+
+    /// The fewest coins from this purse adding up to `cents`, without
+    /// taking them out
+    pub fn make_change(&self, cents: u32) -> Result<CoinPurse, ChangeError> {
+        make_change(cents, self)
+    }
+}
+
+// what `n` of `coin` are worth, in cents
+fn value_of(coin: Coin, n: u32) -> u64 {
+    u64::from(coin.value_in_cents()) * u64::from(n)
+}
+
+// how many fewer than the most that fit the best count of quarters or
+// dimes can be, see the notes at the top
+const QUARTER_SLACK: u32 = 9;
+const DIME_SLACK: u32 = 4;
+
+/// The fewest coins from `available` adding up to `cents`
+pub fn make_change(cents: u32, available: &CoinPurse) -> Result<CoinPurse, ChangeError> {
+    let have = |coin| available.count(coin);
+    let mut best: Option<(u64, [u32; 4])> = None;
+
+    let most_quarters = have(Coin::Quarter).min(cents / 25);
+    for q in (most_quarters.saturating_sub(QUARTER_SLACK)..=most_quarters).rev() {
+        let after_quarters = cents - 25 * q;
+        let most_dimes = have(Coin::Dime).min(after_quarters / 10);
+        for d in (most_dimes.saturating_sub(DIME_SLACK)..=most_dimes).rev() {
+            let after_dimes = after_quarters - 10 * d;
+            // fewer nickels only means more pennies
+            let n = have(Coin::Nickel).min(after_dimes / 5);
+            let p = after_dimes - 5 * n;
+            if p > have(Coin::Penny) {
+                continue;
+            }
+            let coins = [p, n, d, q].iter().map(|&c| u64::from(c)).sum();
+            if best.is_none_or(|(fewest, _)| coins < fewest) {
+                // in Coin::ALL order
+                best = Some((coins, [p, n, d, q]));
+            }
+        }
+    }
+    best.map(|(_, counts)| CoinPurse { counts })
+        .ok_or(ChangeError::ExactChangeImpossible(cents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn purse_arithmetic() {
+        let mut purse = CoinPurse::of(&[(Coin::Quarter, 3), (Coin::Dime, 2), (Coin::Penny, 4)]);
+        assert_eq!(purse.total_cents(), 99);
+        assert_eq!(purse.coin_count(), 9);
+        assert_eq!(
+            purse.take(&CoinPurse::of(&[(Coin::Dime, 3)])),
+            Err(ChangeError::NotEnough {
+                coin: Coin::Dime,
+                have: 2,
+                wanted: 3
+            })
+        );
+        // nothing was taken
+        assert_eq!(purse.total_cents(), 99);
+        purse
+            .take(&CoinPurse::of(&[(Coin::Quarter, 1), (Coin::Penny, 4)]))
+            .unwrap();
+        assert_eq!(
+            purse.iter().collect::<Vec<_>>(),
+            vec![(Coin::Dime, 2), (Coin::Quarter, 2)]
+        );
+    }
+
+    #[test]
+    fn large_counts() {
+        let max = u32::MAX;
+        let mut purse = CoinPurse::of(&[(Coin::Quarter, max), (Coin::Dime, max)]);
+        assert_eq!(purse.total_cents(), 35 * u64::from(max));
+        assert_eq!(purse.coin_count(), 2 * u64::from(max));
+        assert_eq!(
+            purse.make_change(100),
+            Ok(CoinPurse::of(&[(Coin::Quarter, 4)]))
+        );
+        assert_eq!(
+            purse.make_change(1005),
+            Ok(CoinPurse::of(&[(Coin::Quarter, 39), (Coin::Dime, 3)]))
+        );
+
+        assert_eq!(
+            purse.add(Coin::Quarter, 1),
+            Err(ChangeError::TooMany {
+                coin: Coin::Quarter,
+                have: max,
+                adding: 1
+            })
+        );
+        assert_eq!(purse.count(Coin::Quarter), max);
+        // all or nothing: the pennies don't go in either
+        assert!(purse
+            .merge(&CoinPurse::of(&[(Coin::Penny, 1), (Coin::Dime, 1)]))
+            .is_err());
+        assert_eq!(purse.count(Coin::Penny), 0);
+        purse.merge(&CoinPurse::of(&[(Coin::Penny, 1)])).unwrap();
+        assert_eq!(purse.count(Coin::Penny), 1);
+    }
+
+    #[test]
+    fn large_amounts_are_quick() {
+        let max = u32::MAX;
+        let full = CoinPurse::of(&[
+            (Coin::Quarter, max),
+            (Coin::Dime, max),
+            (Coin::Nickel, max),
+            (Coin::Penny, max),
+        ]);
+        let started = std::time::Instant::now();
+        // 4294967295 = 171798691 quarters and 20 cents
+        assert_eq!(
+            full.make_change(max),
+            Ok(CoinPurse::of(&[
+                (Coin::Quarter, 171_798_691),
+                (Coin::Dime, 2)
+            ]))
+        );
+        assert_eq!(
+            full.make_change(100_000_000).unwrap().coin_count(),
+            4_000_000
+        );
+
+        // few quarters: the rest from dimes, then pennies
+        let some = CoinPurse::of(&[(Coin::Quarter, 3), (Coin::Dime, 10), (Coin::Penny, max)]);
+        assert_eq!(
+            some.make_change(max),
+            Ok(CoinPurse::of(&[
+                (Coin::Quarter, 3),
+                (Coin::Dime, 10),
+                (Coin::Penny, max - 175)
+            ]))
+        );
+        assert_eq!(
+            CoinPurse::of(&[(Coin::Dime, max)]).make_change(max),
+            Err(ChangeError::ExactChangeImpossible(max))
+        );
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn change_with_limited_coins() {
+        // greedy would take the quarter and be stuck at 5
+        let purse = CoinPurse::of(&[(Coin::Quarter, 1), (Coin::Dime, 3)]);
+        assert_eq!(purse.make_change(30), Ok(CoinPurse::of(&[(Coin::Dime, 3)])));
+
+        let plenty = CoinPurse::of(&[
+            (Coin::Quarter, 10),
+            (Coin::Dime, 10),
+            (Coin::Nickel, 10),
+            (Coin::Penny, 10),
+        ]);
+        assert_eq!(
+            plenty.make_change(41),
+            Ok(CoinPurse::of(&[
+                (Coin::Quarter, 1),
+                (Coin::Dime, 1),
+                (Coin::Nickel, 1),
+                (Coin::Penny, 1)
+            ]))
+        );
+        assert_eq!(plenty.make_change(0), Ok(CoinPurse::new()));
+
+        let dimes = CoinPurse::of(&[(Coin::Dime, 5)]);
+        assert_eq!(
+            dimes.make_change(15),
+            Err(ChangeError::ExactChangeImpossible(15))
+        );
+        assert_eq!(
+            dimes.make_change(60),
+            Err(ChangeError::ExactChangeImpossible(60))
+        );
+    }
+
+    fn purse() -> impl Strategy<Value = CoinPurse> {
+        prop::array::uniform4(0u32..6).prop_map(|counts| CoinPurse { counts })
+    }
+
+    // every way to pick coins from the purse, for comparing against
+    fn fewest_by_brute_force(cents: u32, available: &CoinPurse) -> Option<u32> {
+        let c = available.counts;
+        let mut best = None;
+        for p in 0..=c[0] {
+            for n in 0..=c[1] {
+                for d in 0..=c[2] {
+                    for q in 0..=c[3] {
+                        if p + 5 * n + 10 * d + 25 * q == cents {
+                            let coins = p + n + d + q;
+                            best = Some(best.map_or(coins, |b: u32| b.min(coins)));
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    // the fewest coins by a table over every amount up to `cents`, one coin
+    // at a time; for purses too big to brute force, past the slack the
+    // search allows
+    fn fewest_by_table(cents: u32, available: &CoinPurse) -> Option<u64> {
+        let amount = cents as usize;
+        let mut fewest: Vec<Option<u64>> = vec![None; amount + 1];
+        fewest[0] = Some(0);
+        for (coin, n) in available.iter() {
+            let value = coin.value_in_cents() as usize;
+            for _ in 0..n {
+                for a in (value..=amount).rev() {
+                    if let Some(before) = fewest[a - value] {
+                        if fewest[a].is_none_or(|best| before + 1 < best) {
+                            fewest[a] = Some(before + 1);
+                        }
+                    }
+                }
+            }
+        }
+        fewest[amount]
+    }
+
+    proptest! {
+        #[test]
+        fn change_is_exact_available_and_fewest(available in purse(), cents in 0u32..200) {
+            match make_change(cents, &available) {
+                Ok(change) => {
+                    prop_assert_eq!(change.total_cents(), u64::from(cents));
+                    let mut rest = available;
+                    prop_assert!(rest.take(&change).is_ok());
+                    prop_assert_eq!(Some(change.coin_count()), fewest_by_brute_force(cents, &available).map(u64::from));
+                }
+                Err(e) => {
+                    prop_assert_eq!(e, ChangeError::ExactChangeImpossible(cents));
+                    prop_assert_eq!(fewest_by_brute_force(cents, &available), None);
+                }
+            }
+        }
+
+        #[test]
+        fn fewest_with_bigger_purses(
+            counts in prop::array::uniform4(0u32..40),
+            cents in 0u32..1200,
+        ) {
+            let available = CoinPurse { counts };
+            let fewest = make_change(cents, &available).ok().map(|c| c.coin_count());
+            prop_assert_eq!(fewest, fewest_by_table(cents, &available));
+        }
+    }
+}
@@ -0,0 +1,604 @@
+// State quarters
+/*
+Every quarter from the 50 State Quarters program (1999-2008, five a year
+in the order the states joined the Union) and the 2009 DC and territories
+quarters, for the UsState from `enums::pattern_bind`
+
+- each design was struck at Philadelphia (P) and Denver (D) for
+  circulation and at San Francisco (S) as proofs
+- a QuarterCollection counts coins per state and mint; completion is
+  measured against the mints it was made with, P and D by default
+- the checklist file is one `code mint count` line per coin, postal codes
+  for the states (AL P 2), `#` starts a comment
+ */
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+// in release order, so the i-th state came out in 1999 + i / 5
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UsState {
+    Delaware,
+    Pennsylvania,
+    NewJersey,
+    Georgia,
+    Connecticut,
+    Massachusetts,
+    Maryland,
+    SouthCarolina,
+    NewHampshire,
+    Virginia,
+    NewYork,
+    NorthCarolina,
+    RhodeIsland,
+    Vermont,
+    Kentucky,
+    Tennessee,
+    Ohio,
+    Louisiana,
+    Indiana,
+    Mississippi,
+    Illinois,
+    Alabama,
+    Maine,
+    Missouri,
+    Arkansas,
+    Michigan,
+    Florida,
+    Texas,
+    Iowa,
+    Wisconsin,
+    California,
+    Minnesota,
+    Oregon,
+    Kansas,
+    WestVirginia,
+    Nevada,
+    Nebraska,
+    Colorado,
+    NorthDakota,
+    SouthDakota,
+    Montana,
+    Washington,
+    Idaho,
+    Wyoming,
+    Utah,
+    Oklahoma,
+    NewMexico,
+    Arizona,
+    Alaska,
+    Hawaii,
+    DistrictOfColumbia,
+    PuertoRico,
+    Guam,
+    AmericanSamoa,
+    VirginIslands,
+    NorthernMarianaIslands,
+}
+
+use UsState::*;
+
+// (state, name, postal code) in release order
+const STATES: [(UsState, &str, &str); 56] = [
+    (Delaware, "Delaware", "DE"),
+    (Pennsylvania, "Pennsylvania", "PA"),
+    (NewJersey, "New Jersey", "NJ"),
+    (Georgia, "Georgia", "GA"),
+    (Connecticut, "Connecticut", "CT"),
+    (Massachusetts, "Massachusetts", "MA"),
+    (Maryland, "Maryland", "MD"),
+    (SouthCarolina, "South Carolina", "SC"),
+    (NewHampshire, "New Hampshire", "NH"),
+    (Virginia, "Virginia", "VA"),
+    (NewYork, "New York", "NY"),
+    (NorthCarolina, "North Carolina", "NC"),
+    (RhodeIsland, "Rhode Island", "RI"),
+    (Vermont, "Vermont", "VT"),
+    (Kentucky, "Kentucky", "KY"),
+    (Tennessee, "Tennessee", "TN"),
+    (Ohio, "Ohio", "OH"),
+    (Louisiana, "Louisiana", "LA"),
+    (Indiana, "Indiana", "IN"),
+    (Mississippi, "Mississippi", "MS"),
+    (Illinois, "Illinois", "IL"),
+    (Alabama, "Alabama", "AL"),
+    (Maine, "Maine", "ME"),
+    (Missouri, "Missouri", "MO"),
+    (Arkansas, "Arkansas", "AR"),
+    (Michigan, "Michigan", "MI"),
+    (Florida, "Florida", "FL"),
+    (Texas, "Texas", "TX"),
+    (Iowa, "Iowa", "IA"),
+    (Wisconsin, "Wisconsin", "WI"),
+    (California, "California", "CA"),
+    (Minnesota, "Minnesota", "MN"),
+    (Oregon, "Oregon", "OR"),
+    (Kansas, "Kansas", "KS"),
+    (WestVirginia, "West Virginia", "WV"),
+    (Nevada, "Nevada", "NV"),
+    (Nebraska, "Nebraska", "NE"),
+    (Colorado, "Colorado", "CO"),
+    (NorthDakota, "North Dakota", "ND"),
+    (SouthDakota, "South Dakota", "SD"),
+    (Montana, "Montana", "MT"),
+    (Washington, "Washington", "WA"),
+    (Idaho, "Idaho", "ID"),
+    (Wyoming, "Wyoming", "WY"),
+    (Utah, "Utah", "UT"),
+    (Oklahoma, "Oklahoma", "OK"),
+    (NewMexico, "New Mexico", "NM"),
+    (Arizona, "Arizona", "AZ"),
+    (Alaska, "Alaska", "AK"),
+    (Hawaii, "Hawaii", "HI"),
+    (DistrictOfColumbia, "District of Columbia", "DC"),
+    (PuertoRico, "Puerto Rico", "PR"),
+    (Guam, "Guam", "GU"),
+    (AmericanSamoa, "American Samoa", "AS"),
+    (VirginIslands, "U.S. Virgin Islands", "VI"),
+    (NorthernMarianaIslands, "Northern Mariana Islands", "MP"),
+];
+
+impl UsState {
+    pub fn all() -> impl Iterator<Item = UsState> {
+        STATES.iter().map(|&(state, _, _)| state)
+    }
+
+    pub fn name(&self) -> &'static str {
+        STATES[*self as usize].1
+    }
+
+    pub fn code(&self) -> &'static str {
+        STATES[*self as usize].2
+    }
+
+    pub fn from_code(code: &str) -> Option<UsState> {
+        STATES
+            .iter()
+            .find(|(_, _, c)| c.eq_ignore_ascii_case(code))
+            .map(|&(state, _, _)| state)
+    }
+
+    /// DC and the territories aren't states
+    pub fn is_state(&self) -> bool {
+        (*self as usize) < 50
+    }
+
+    pub fn release_year(&self) -> u16 {
+        if self.is_state() {
+            1999 + *self as u16 / 5
+        } else {
+            2009
+        }
+    }
+}
+
+impl fmt::Display for UsState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Mint {
+    Philadelphia,
+    Denver,
+    SanFrancisco,
+}
+
+impl Mint {
+    pub const ALL: [Mint; 3] = [Mint::Philadelphia, Mint::Denver, Mint::SanFrancisco];
+
+    pub fn mark(&self) -> char {
+        match self {
+            Mint::Philadelphia => 'P',
+            Mint::Denver => 'D',
+            Mint::SanFrancisco => 'S',
+        }
+    }
+
+    pub fn from_mark(mark: char) -> Option<Mint> {
+        Mint::ALL
+            .iter()
+            .copied()
+            .find(|m| m.mark() == mark.to_ascii_uppercase())
+    }
+}
+
+#[derive(Debug)]
+pub enum ChecklistError {
+    UnknownState(String),
+    UnknownMint(String),
+    InvalidCount(String),
+    // not three fields
+    Malformed(String),
+    // adding would take a count past u32::MAX
+    CountOverflow(UsState, Mint),
+    AtLine {
+        line: usize,
+        error: Box<ChecklistError>,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for ChecklistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChecklistError::UnknownState(s) => write!(f, "unknown state code {:?}", s),
+            ChecklistError::UnknownMint(s) => write!(f, "unknown mint mark {:?}", s),
+            ChecklistError::InvalidCount(s) => write!(f, "invalid count {:?}", s),
+            ChecklistError::Malformed(s) => write!(f, "expected `code mint count`, got {:?}", s),
+            ChecklistError::CountOverflow(state, mint) => {
+                write!(f, "too many {} {} quarters", state.code(), mint.mark())
+            }
+            // the error itself is the source
+            ChecklistError::AtLine { line, .. } => write!(f, "line {}", line),
+            ChecklistError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ChecklistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ChecklistError::AtLine { error, .. } => Some(error.as_ref()),
+            ChecklistError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ChecklistError {
+    fn from(e: io::Error) -> ChecklistError {
+        ChecklistError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarterCollection {
+    mints: Vec<Mint>,
+    coins: BTreeMap<(UsState, Mint), u32>,
+}
+
+impl Default for QuarterCollection {
+    fn default() -> QuarterCollection {
+        QuarterCollection::with_mints(&[Mint::Philadelphia, Mint::Denver])
+    }
+}
+
+impl QuarterCollection {
+    pub fn new() -> QuarterCollection {
+        QuarterCollection::default()
+    }
+
+    /// A collection that is complete with one of each design from each mint
+    pub fn with_mints(mints: &[Mint]) -> QuarterCollection {
+        QuarterCollection {
+            mints: mints.to_vec(),
+            coins: BTreeMap::new(),
+        }
+    }
+
+    /// Adds one coin, CountOverflow if there are already u32::MAX
+    pub fn add(&mut self, state: UsState, mint: Mint) -> Result<(), ChecklistError> {
+        self.add_many(state, mint, 1)
+    }
+
+    /// Adds `n` coins, or nothing and CountOverflow if the count would go
+    /// past u32::MAX
+    pub fn add_many(&mut self, state: UsState, mint: Mint, n: u32) -> Result<(), ChecklistError> {
+        let total = self
+            .count(state, mint)
+            .checked_add(n)
+            .ok_or(ChecklistError::CountOverflow(state, mint))?;
+        if total > 0 {
+            self.coins.insert((state, mint), total);
+        }
+        Ok(())
+    }
+
+    /// Takes one coin out, false if there wasn't one
+    pub fn remove(&mut self, state: UsState, mint: Mint) -> bool {
+        match self.coins.get_mut(&(state, mint)) {
+            Some(n) if *n > 1 => *n -= 1,
+            Some(_) => {
+                self.coins.remove(&(state, mint));
+            }
+            None => return false,
+        }
+        true
+    }
+
+    pub fn count(&self, state: UsState, mint: Mint) -> u32 {
+        self.coins.get(&(state, mint)).copied().unwrap_or(0)
+    }
+
+    /// Every (state, mint) with at least one coin, any mint
+    pub fn owned(&self) -> impl Iterator<Item = (UsState, Mint)> + '_ {
+        self.coins.keys().copied()
+    }
+
+    /// The spare coins: how many beyond the first
+    pub fn duplicates(&self) -> impl Iterator<Item = (UsState, Mint, u32)> + '_ {
+        self.coins
+            .iter()
+            .filter(|(_, &n)| n > 1)
+            .map(|(&(state, mint), &n)| (state, mint, n - 1))
+    }
+
+    /// Every (state, mint) still needed, in release order
+    pub fn missing(&self) -> Vec<(UsState, Mint)> {
+        self.wanted()
+            .filter(|&(state, mint)| self.count(state, mint) == 0)
+            .collect()
+    }
+
+    fn wanted(&self) -> impl Iterator<Item = (UsState, Mint)> + '_ {
+        UsState::all().flat_map(move |state| self.mints.iter().map(move |&mint| (state, mint)))
+    }
+
+    /// Percentage of the collection's mints that are owned
+    pub fn completion(&self) -> f64 {
+        self.completion_where(|_, _| true)
+    }
+
+    /// Percentage owned out of the coins `include` picks, e.g.
+    /// `|state, _| state.release_year() == 2003`; 100 when it picks none
+    pub fn completion_where<F>(&self, mut include: F) -> f64
+    where
+        F: FnMut(UsState, Mint) -> bool,
+    {
+        let (mut wanted, mut have) = (0, 0);
+        for (state, mint) in self.wanted().filter(|&(s, m)| include(s, m)) {
+            wanted += 1;
+            if self.count(state, mint) > 0 {
+                have += 1;
+            }
+        }
+        if wanted == 0 {
+            return 100.0;
+        }
+        100.0 * f64::from(have) / f64::from(wanted)
+    }
+
+    /// The checklist text: every wanted coin, owned or not, then any
+    /// others owned
+    pub fn to_checklist(&self) -> String {
+        let mut out = String::from("# state quarters: code mint count\n");
+        let extra = self.owned().filter(|(_, mint)| !self.mints.contains(mint));
+        let mut entries: Vec<(UsState, Mint)> = self.wanted().chain(extra).collect();
+        entries.sort();
+        for (state, mint) in entries {
+            out.push_str(&format!(
+                "{} {} {}\n",
+                state.code(),
+                mint.mark(),
+                self.count(state, mint)
+            ));
+        }
+        out
+    }
+
+    /// Adds the counts in a checklist to the ones already in this
+    /// collection, it doesn't replace them; a line that would overflow a
+    /// count is a CountOverflow error
+    pub fn import_checklist(&mut self, text: &str) -> Result<(), ChecklistError> {
+        let mut totals = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |error| ChecklistError::AtLine {
+                line: i + 1,
+                error: Box::new(error),
+            };
+            let (state, mint, n) = parse_entry(line).map_err(at_line)?;
+            let total = totals
+                .entry((state, mint))
+                .or_insert_with(|| self.count(state, mint));
+            *total = total
+                .checked_add(n)
+                .ok_or_else(|| at_line(ChecklistError::CountOverflow(state, mint)))?;
+        }
+        // only once every line is good
+        for (key, total) in totals {
+            if total > 0 {
+                self.coins.insert(key, total);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ChecklistError> {
+        fs::write(path, self.to_checklist())?;
+        Ok(())
+    }
+
+    pub fn load(&mut self, path: &Path) -> Result<(), ChecklistError> {
+        self.import_checklist(&fs::read_to_string(path)?)
+    }
+}
+
+fn parse_entry(line: &str) -> Result<(UsState, Mint, u32), ChecklistError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (code, mark, count) = match fields[..] {
+        [code, mark, count] => (code, mark, count),
+        _ => return Err(ChecklistError::Malformed(String::from(line))),
+    };
+    let state =
+        UsState::from_code(code).ok_or_else(|| ChecklistError::UnknownState(String::from(code)))?;
+    let mut chars = mark.chars();
+    let mint = match (chars.next(), chars.next()) {
+        (Some(c), None) => Mint::from_mark(c),
+        _ => None,
+    }
+    .ok_or_else(|| ChecklistError::UnknownMint(String::from(mark)))?;
+    let n = u32::from_str(count).map_err(|_| ChecklistError::InvalidCount(String::from(count)))?;
+    Ok((state, mint, n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn states_and_years() {
+        assert_eq!(UsState::all().count(), 56);
+        assert_eq!(UsState::all().filter(UsState::is_state).count(), 50);
+        assert_eq!(Delaware.release_year(), 1999);
+        assert_eq!(Alabama.release_year(), 2003);
+        assert_eq!(Hawaii.release_year(), 2008);
+        assert_eq!(Guam.release_year(), 2009);
+        for year in 1999..=2008 {
+            assert_eq!(
+                UsState::all().filter(|s| s.release_year() == year).count(),
+                5
+            );
+        }
+        assert_eq!(UsState::from_code("wv"), Some(WestVirginia));
+        assert_eq!(UsState::from_code("XX"), None);
+        assert_eq!(VirginIslands.to_string(), "U.S. Virgin Islands");
+        assert_eq!(Mint::from_mark('d'), Some(Mint::Denver));
+    }
+
+    #[test]
+    fn tracks_owned_duplicates_and_missing() {
+        let mut c = QuarterCollection::new();
+        c.add(Alabama, Mint::Philadelphia).unwrap();
+        c.add(Alabama, Mint::Philadelphia).unwrap();
+        c.add(Alaska, Mint::Denver).unwrap();
+        c.add(Alaska, Mint::SanFrancisco).unwrap();
+        assert_eq!(
+            c.duplicates().collect::<Vec<_>>(),
+            vec![(Alabama, Mint::Philadelphia, 1)]
+        );
+        assert_eq!(c.owned().count(), 3);
+        assert_eq!(c.missing().len(), 112 - 2);
+        assert!(!c.missing().contains(&(Alaska, Mint::Denver)));
+        // the proof doesn't count towards P and D
+        assert!((c.completion() - 200.0 / 112.0).abs() < 1e-9);
+        assert_eq!(
+            c.completion_where(|state, _| state.release_year() == 2003),
+            10.0
+        );
+        assert_eq!(c.completion_where(|_, _| false), 100.0);
+
+        assert!(c.remove(Alabama, Mint::Philadelphia));
+        assert!(c.remove(Alabama, Mint::Philadelphia));
+        assert!(!c.remove(Alabama, Mint::Philadelphia));
+        assert_eq!(c.duplicates().count(), 0);
+    }
+
+    #[test]
+    fn complete_for_its_mints() {
+        let mut c = QuarterCollection::with_mints(&[Mint::SanFrancisco]);
+        for state in UsState::all() {
+            c.add(state, Mint::SanFrancisco).unwrap();
+        }
+        assert!(c.missing().is_empty());
+        assert_eq!(c.completion(), 100.0);
+    }
+
+    #[test]
+    fn checklist_round_trip() {
+        let mut c = QuarterCollection::new();
+        c.add_many(Delaware, Mint::Denver, 3).unwrap();
+        c.add(Guam, Mint::SanFrancisco).unwrap();
+        let text = c.to_checklist();
+        assert!(text.contains("DE D 3\n"));
+        assert!(text.contains("DE P 0\n"));
+        assert!(text.contains("GU S 1\n"));
+        assert_eq!(text.lines().count(), 1 + 112 + 1);
+
+        let mut copy = QuarterCollection::new();
+        copy.import_checklist(&text).unwrap();
+        assert_eq!(copy, c);
+    }
+
+    #[test]
+    fn checklist_errors_name_the_line() {
+        let mut c = QuarterCollection::new();
+        let text = "# mine\nAL P 1\n\nAK Q 1\n";
+        match c.import_checklist(text) {
+            Err(ChecklistError::AtLine { line: 4, error }) => {
+                assert!(matches!(*error, ChecklistError::UnknownMint(ref m) if m == "Q"));
+            }
+            other => panic!("{:?}", other),
+        }
+        // nothing from a bad file goes in
+        assert_eq!(c.owned().count(), 0);
+
+        for (bad, check) in &[
+            ("ZZ P 1", "unknown state"),
+            ("AL PD 1", "unknown mint"),
+            ("AL P -1", "invalid count"),
+            ("AL P", "expected"),
+        ] {
+            let e = c.import_checklist(bad).unwrap_err();
+            // the line here, what was wrong with it in source()
+            assert_eq!(e.to_string(), "line 1");
+            let cause = e.source().unwrap().to_string();
+            assert!(cause.contains(check), "{}: {}", bad, cause);
+        }
+    }
+
+    #[test]
+    fn import_adds_to_existing_counts() {
+        let mut c = QuarterCollection::new();
+        c.add_many(Alabama, Mint::Philadelphia, 2).unwrap();
+        c.import_checklist("AL P 3\nAL P 1\nAK D 0\n").unwrap();
+        assert_eq!(c.count(Alabama, Mint::Philadelphia), 6);
+        assert_eq!(c.count(Alaska, Mint::Denver), 0);
+    }
+
+    #[test]
+    fn counts_do_not_overflow() {
+        let mut c = QuarterCollection::new();
+        c.add_many(Alabama, Mint::Philadelphia, u32::MAX).unwrap();
+        assert!(matches!(
+            c.add(Alabama, Mint::Philadelphia),
+            Err(ChecklistError::CountOverflow(Alabama, Mint::Philadelphia))
+        ));
+        assert_eq!(c.count(Alabama, Mint::Philadelphia), u32::MAX);
+
+        let mut c = QuarterCollection::new();
+        c.add(Texas, Mint::Denver).unwrap();
+        let text = "TX D 4\nAL P 4294967295\nAL P 1\n";
+        match c.import_checklist(text) {
+            Err(ChecklistError::AtLine { line: 3, error }) => {
+                assert!(matches!(
+                    *error,
+                    ChecklistError::CountOverflow(Alabama, Mint::Philadelphia)
+                ));
+                assert_eq!(error.to_string(), "too many AL P quarters");
+            }
+            other => panic!("{:?}", other),
+        }
+        // nothing from the file went in
+        assert_eq!(c.count(Texas, Mint::Denver), 1);
+        assert_eq!(c.count(Alabama, Mint::Philadelphia), 0);
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = TempDir::new("quarters");
+        let path = dir.join("checklist.txt");
+
+        let mut c = QuarterCollection::new();
+        c.add(Texas, Mint::Philadelphia).unwrap();
+        c.save(&path).unwrap();
+        let mut loaded = QuarterCollection::new();
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded, c);
+
+        assert!(matches!(
+            loaded.load(&dir.join("missing.txt")),
+            Err(ChecklistError::Io(_))
+        ));
+    }
+}
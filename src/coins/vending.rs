@@ -0,0 +1,308 @@
+// Vending machine
+/*
+A coin-operated machine as a state machine: Idle until a coin goes in,
+then Paying until an item comes out or the customer cancels
+
+- coins the machine doesn't take (pennies by default) or that would push
+  the credit over the limit are rejected and handed back
+- change comes from the bank plus the coins just inserted, using
+  make_change so a limited bank is handled properly
+- when exact change can't be made nothing is sold and the credit stays,
+  so the customer can pick something else or cancel
+- cancel hands back exactly the coins that were put in
+ */
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use super::{Coin, CoinPurse};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VendError {
+    Rejected { coin: Coin, reason: &'static str },
+    UnknownItem(String),
+    SoldOut(String),
+    InsufficientCredit { price: u32, credit: u32 },
+    CannotMakeChange(u32),
+}
+
+impl fmt::Display for VendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VendError::Rejected { coin, reason } => write!(f, "{} rejected: {}", coin, reason),
+            VendError::UnknownItem(code) => write!(f, "no item {}", code),
+            VendError::SoldOut(code) => write!(f, "{} is sold out", code),
+            VendError::InsufficientCredit { price, credit } => {
+                write!(f, "costs {} cents, credit is {}", price, credit)
+            }
+            VendError::CannotMakeChange(cents) => {
+                write!(f, "exact change only, can't return {} cents", cents)
+            }
+        }
+    }
+}
+
+impl Error for VendError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    pub name: String,
+    pub price: u32,
+    pub stock: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VendState {
+    Idle,
+    Paying { inserted: CoinPurse },
+}
+
+/// What comes out of the machine after a sale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vend {
+    pub item: String,
+    pub change: CoinPurse,
+}
+
+#[derive(Debug, Clone)]
+pub struct VendingMachine {
+    slots: BTreeMap<String, Slot>,
+    bank: CoinPurse,
+    accepts: Vec<Coin>,
+    max_credit: u32,
+    state: VendState,
+}
+
+impl VendingMachine {
+    /// A machine starting with `bank` for change, taking nickels, dimes
+    /// and quarters up to $5
+    pub fn new(bank: CoinPurse) -> VendingMachine {
+        VendingMachine {
+            slots: BTreeMap::new(),
+            bank,
+            accepts: vec![Coin::Nickel, Coin::Dime, Coin::Quarter],
+            max_credit: 500,
+            state: VendState::Idle,
+        }
+    }
+
+    pub fn accepting(mut self, coins: &[Coin]) -> VendingMachine {
+        self.accepts = coins.to_vec();
+        self
+    }
+
+    pub fn with_max_credit(mut self, cents: u32) -> VendingMachine {
+        self.max_credit = cents;
+        self
+    }
+
+    /// Fills slot `code`, replacing what was there
+    pub fn stock(&mut self, code: &str, name: &str, price: u32, count: u32) {
+        self.slots.insert(
+            String::from(code),
+            Slot {
+                name: String::from(name),
+                price,
+                stock: count,
+            },
+        );
+    }
+
+    pub fn slot(&self, code: &str) -> Option<&Slot> {
+        self.slots.get(code)
+    }
+
+    pub fn state(&self) -> &VendState {
+        &self.state
+    }
+
+    pub fn bank(&self) -> &CoinPurse {
+        &self.bank
+    }
+
+    pub fn credit(&self) -> u32 {
+        match &self.state {
+            VendState::Idle => 0,
+            VendState::Paying { inserted } => {
+                u32::try_from(inserted.total_cents()).expect("credit is capped at max_credit")
+            }
+        }
+    }
+
+    /// Takes a coin and returns the credit so far
+    pub fn insert(&mut self, coin: Coin) -> Result<u32, VendError> {
+        if !self.accepts.contains(&coin) {
+            return Err(VendError::Rejected {
+                coin,
+                reason: "not accepted",
+            });
+        }
+        if self.credit() + coin.value_in_cents() > self.max_credit {
+            return Err(VendError::Rejected {
+                coin,
+                reason: "over the credit limit",
+            });
+        }
+        match &mut self.state {
+            VendState::Idle => {
+                self.state = VendState::Paying {
+                    inserted: CoinPurse::of(&[(coin, 1)]),
+                }
+            }
+            VendState::Paying { inserted } => inserted
+                .add(coin, 1)
+                .expect("credit is capped at max_credit"),
+        }
+        Ok(self.credit())
+    }
+
+    pub fn select(&mut self, code: &str) -> Result<Vend, VendError> {
+        let slot = self
+            .slots
+            .get(code)
+            .ok_or_else(|| VendError::UnknownItem(String::from(code)))?;
+        if slot.stock == 0 {
+            return Err(VendError::SoldOut(String::from(code)));
+        }
+        let credit = self.credit();
+        if credit < slot.price {
+            return Err(VendError::InsufficientCredit {
+                price: slot.price,
+                credit,
+            });
+        }
+
+        let owed = credit - slot.price;
+        let mut pool = self.bank;
+        if let VendState::Paying { inserted } = &self.state {
+            // a bank that full can't take the coins to pay with
+            pool.merge(inserted)
+                .map_err(|_| VendError::CannotMakeChange(owed))?;
+        }
+        let change = pool
+            .make_change(owed)
+            .map_err(|_| VendError::CannotMakeChange(owed))?;
+        pool.take(&change).expect("change comes out of the pool");
+
+        let slot = self.slots.get_mut(code).expect("looked up above");
+        slot.stock -= 1;
+        self.bank = pool;
+        self.state = VendState::Idle;
+        Ok(Vend {
+            item: slot.name.clone(),
+            change,
+        })
+    }
+
+    /// Hands back the coins inserted so far
+    pub fn cancel(&mut self) -> CoinPurse {
+        match std::mem::replace(&mut self.state, VendState::Idle) {
+            VendState::Idle => CoinPurse::new(),
+            VendState::Paying { inserted } => inserted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine(bank: CoinPurse) -> VendingMachine {
+        let mut m = VendingMachine::new(bank);
+        m.stock("A1", "crisps", 65, 2);
+        m.stock("B2", "water", 100, 0);
+        m
+    }
+
+    #[test]
+    fn buys_with_change() {
+        let mut m = machine(CoinPurse::of(&[(Coin::Dime, 5), (Coin::Nickel, 5)]));
+        assert_eq!(m.state(), &VendState::Idle);
+        for coin in &[Coin::Quarter, Coin::Quarter, Coin::Quarter] {
+            m.insert(*coin).unwrap();
+        }
+        assert_eq!(m.credit(), 75);
+        let vend = m.select("A1").unwrap();
+        assert_eq!(vend.item, "crisps");
+        assert_eq!(vend.change, CoinPurse::of(&[(Coin::Dime, 1)]));
+        assert_eq!(m.state(), &VendState::Idle);
+        assert_eq!(m.slot("A1").unwrap().stock, 1);
+        assert_eq!(m.bank().count(Coin::Quarter), 3);
+        assert_eq!(m.bank().count(Coin::Dime), 4);
+    }
+
+    #[test]
+    fn change_can_come_from_the_inserted_coins() {
+        let mut m = machine(CoinPurse::new());
+        for _ in 0..7 {
+            m.insert(Coin::Dime).unwrap();
+        }
+        m.insert(Coin::Nickel).unwrap();
+        // 75 in, 65 owed: one of its own dimes back
+        assert_eq!(
+            m.select("A1").unwrap().change,
+            CoinPurse::of(&[(Coin::Dime, 1)])
+        );
+        assert_eq!(m.bank().total_cents(), 65);
+    }
+
+    #[test]
+    fn rejects_coins() {
+        let mut m = machine(CoinPurse::new()).with_max_credit(50);
+        assert_eq!(
+            m.insert(Coin::Penny),
+            Err(VendError::Rejected {
+                coin: Coin::Penny,
+                reason: "not accepted"
+            })
+        );
+        assert_eq!(m.state(), &VendState::Idle);
+        m.insert(Coin::Quarter).unwrap();
+        m.insert(Coin::Quarter).unwrap();
+        assert!(matches!(
+            m.insert(Coin::Nickel),
+            Err(VendError::Rejected {
+                coin: Coin::Nickel,
+                ..
+            })
+        ));
+        assert_eq!(m.credit(), 50);
+
+        let mut pennies = machine(CoinPurse::new()).accepting(&[Coin::Penny]);
+        assert_eq!(pennies.insert(Coin::Penny), Ok(1));
+    }
+
+    #[test]
+    fn refuses_to_sell() {
+        let mut m = machine(CoinPurse::new());
+        assert_eq!(
+            m.select("Z9"),
+            Err(VendError::UnknownItem(String::from("Z9")))
+        );
+        assert_eq!(m.select("B2"), Err(VendError::SoldOut(String::from("B2"))));
+        m.insert(Coin::Quarter).unwrap();
+        assert_eq!(
+            m.select("A1"),
+            Err(VendError::InsufficientCredit {
+                price: 65,
+                credit: 25
+            })
+        );
+    }
+
+    #[test]
+    fn exact_change_only_keeps_the_credit() {
+        let mut m = machine(CoinPurse::new());
+        for _ in 0..3 {
+            m.insert(Coin::Quarter).unwrap();
+        }
+        assert_eq!(m.select("A1"), Err(VendError::CannotMakeChange(10)));
+        assert_eq!(m.credit(), 75);
+        assert_eq!(m.slot("A1").unwrap().stock, 2);
+        assert_eq!(m.cancel(), CoinPurse::of(&[(Coin::Quarter, 3)]));
+        assert_eq!(m.state(), &VendState::Idle);
+        assert_eq!(m.bank(), &CoinPurse::new());
+        assert_eq!(m.cancel(), CoinPurse::new());
+    }
+}
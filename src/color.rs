@@ -0,0 +1,508 @@
+// Color
+/*
+A real Color, grown out of `struct Color(i32, i32, i32)` in
+`structs::tuple_struct_simple`
+
+- channels are u8, so always 0-255; coming from i32 (like the tuple
+  struct) is checked, see TryFrom<(i32, i32, i32)>
+- alpha is optional: None is opaque and isn't written out
+- parses and formats hex (#RGB, #RGBA, #RRGGBB, #RRGGBBAA) and CSS
+  rgb(r, g, b) / rgba(r, g, b, a) with a from 0 to 1
+- HSL and HSV use hue in degrees 0-360, the rest 0-1
+- contrast follows WCAG 2: relative luminance of the sRGB channels,
+  alpha is ignored
+ */
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorError {
+    ChannelOutOfRange(i32),
+    InvalidHex(String),
+    InvalidRgb(String),
+    // neither hex nor rgb()
+    Unrecognized(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::ChannelOutOfRange(v) => write!(f, "channel {} is not within 0-255", v),
+            ColorError::InvalidHex(s) => write!(f, "invalid hex color {:?}", s),
+            ColorError::InvalidRgb(s) => write!(f, "invalid rgb() color {:?}", s),
+            ColorError::Unrecognized(s) => write!(f, "unrecognized color {:?}", s),
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub alpha: Option<u8>,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color {
+            r,
+            g,
+            b,
+            alpha: None,
+        }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, alpha: u8) -> Color {
+        Color {
+            r,
+            g,
+            b,
+            alpha: Some(alpha),
+        }
+    }
+
+    pub fn with_alpha(self, alpha: Option<u8>) -> Color {
+        Color { alpha, ..self }
+    }
+
+    // alpha from 0.0 to 1.0, opaque when there is none
+    pub fn opacity(&self) -> f64 {
+        self.alpha.map_or(1.0, |a| f64::from(a) / 255.0)
+    }
+
+    /// #rrggbb, or #rrggbbaa with an alpha
+    pub fn to_hex(&self) -> String {
+        match self.alpha {
+            None => format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b),
+            Some(a) => format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, a),
+        }
+    }
+
+    /// rgb(r, g, b), or rgba(r, g, b, a) with an alpha
+    pub fn to_css(&self) -> String {
+        match self.alpha {
+            None => format!("rgb({}, {}, {})", self.r, self.g, self.b),
+            Some(_) => {
+                // at most 3 decimals, without trailing zeros
+                let a = format!("{:.3}", self.opacity());
+                let a = a.trim_end_matches('0').trim_end_matches('.');
+                format!("rgba({}, {}, {}, {})", self.r, self.g, self.b, a)
+            }
+        }
+    }
+
+    pub fn parse_hex(s: &str) -> Result<Color, ColorError> {
+        let invalid = || ColorError::InvalidHex(String::from(s));
+        let digits = s.strip_prefix('#').ok_or_else(invalid)?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        // every character is ASCII now, so byte slicing is safe
+        let channel = |i: usize, width: usize| {
+            let v = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+            // #abc is #aabbcc
+            if width == 1 {
+                v * 17
+            } else {
+                v
+            }
+        };
+        match digits.len() {
+            3 => Ok(Color::rgb(channel(0, 1), channel(1, 1), channel(2, 1))),
+            4 => Ok(Color::rgba(
+                channel(0, 1),
+                channel(1, 1),
+                channel(2, 1),
+                channel(3, 1),
+            )),
+            6 => Ok(Color::rgb(channel(0, 2), channel(1, 2), channel(2, 2))),
+            8 => Ok(Color::rgba(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                channel(3, 2),
+            )),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn parse_rgb(s: &str) -> Result<Color, ColorError> {
+        let invalid = || ColorError::InvalidRgb(String::from(s));
+        let (args, with_alpha) = if let Some(rest) = s.strip_prefix("rgba(") {
+            (rest, true)
+        } else if let Some(rest) = s.strip_prefix("rgb(") {
+            (rest, false)
+        } else {
+            return Err(invalid());
+        };
+        let args = args.strip_suffix(')').ok_or_else(invalid)?;
+        let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        if parts.len() != if with_alpha { 4 } else { 3 } {
+            return Err(invalid());
+        }
+        let channel = |part: &str| -> Result<u8, ColorError> {
+            let v: i32 = part.parse().map_err(|_| invalid())?;
+            u8::try_from(v).map_err(|_| ColorError::ChannelOutOfRange(v))
+        };
+        let mut color = Color::rgb(channel(parts[0])?, channel(parts[1])?, channel(parts[2])?);
+        if with_alpha {
+            let a: f64 = parts[3].parse().map_err(|_| invalid())?;
+            if !(0.0..=1.0).contains(&a) {
+                return Err(invalid());
+            }
+            color.alpha = Some((a * 255.0).round() as u8);
+        }
+        Ok(color)
+    }
+
+    /// Linear mix in sRGB: 0.0 is `self`, 1.0 is `other`; `t` is clamped
+    ///
+    /// Alpha is mixed too, the result only has one if either side does.
+    pub fn mix(&self, other: &Color, t: f64) -> Color {
+        let t = clamp01(t);
+        let lerp = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+        let alpha = match (self.alpha, other.alpha) {
+            (None, None) => None,
+            (a, b) => Some(lerp(a.unwrap_or(255), b.unwrap_or(255))),
+        };
+        Color {
+            r: lerp(self.r, other.r),
+            g: lerp(self.g, other.g),
+            b: lerp(self.b, other.b),
+            alpha,
+        }
+    }
+
+    /// Paints `self` over `background` (source-over compositing)
+    pub fn over(&self, background: &Color) -> Color {
+        let (fa, ba) = (self.opacity(), background.opacity());
+        let out = fa + ba * (1.0 - fa);
+        if out == 0.0 {
+            return Color::rgba(0, 0, 0, 0);
+        }
+        let channel = |f: u8, b: u8| {
+            ((f64::from(f) * fa + f64::from(b) * ba * (1.0 - fa)) / out).round() as u8
+        };
+        let alpha = if self.alpha.is_none() || background.alpha.is_none() {
+            None
+        } else {
+            Some((out * 255.0).round() as u8)
+        };
+        Color {
+            r: channel(self.r, background.r),
+            g: channel(self.g, background.g),
+            b: channel(self.b, background.b),
+            alpha,
+        }
+    }
+
+    /// WCAG 2 relative luminance, 0.0 for black to 1.0 for white
+    pub fn relative_luminance(&self) -> f64 {
+        let linear = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.039_28 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG 2 contrast ratio, from 1.0 (none) to 21.0 (black on white)
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        let (light, dark) = if a > b { (a, b) } else { (b, a) };
+        (light + 0.05) / (dark + 0.05)
+    }
+
+    /// WCAG AA: 4.5 for body text, 3.0 for large text
+    pub fn meets_aa(&self, other: &Color, large_text: bool) -> bool {
+        self.contrast_ratio(other) >= if large_text { 3.0 } else { 4.5 }
+    }
+
+    pub fn to_hsl(&self) -> Hsl {
+        Hsl::from(*self)
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        Hsv::from(*self)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&self.to_hex())
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Color, ColorError> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            Color::parse_hex(s)
+        } else if s.starts_with("rgb") {
+            Color::parse_rgb(s)
+        } else {
+            Err(ColorError::Unrecognized(String::from(s)))
+        }
+    }
+}
+
+// the tuple struct from the chapter: Color(i32, i32, i32)
+impl TryFrom<(i32, i32, i32)> for Color {
+    type Error = ColorError;
+
+    fn try_from((r, g, b): (i32, i32, i32)) -> Result<Color, ColorError> {
+        let channel = |v: i32| u8::try_from(v).map_err(|_| ColorError::ChannelOutOfRange(v));
+        Ok(Color::rgb(channel(r)?, channel(g)?, channel(b)?))
+    }
+}
+
+impl From<Color> for (i32, i32, i32) {
+    fn from(c: Color) -> (i32, i32, i32) {
+        (i32::from(c.r), i32::from(c.g), i32::from(c.b))
+    }
+}
+
+// rgb as 0-1 floats, with the max, min and hue every conversion needs
+fn hue_max_min(c: &Color) -> (f64, f64, f64) {
+    let (r, g, b) = (
+        f64::from(c.r) / 255.0,
+        f64::from(c.g) / 255.0,
+        f64::from(c.b) / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, max, min)
+}
+
+// back from hue, chroma and the smallest channel
+fn from_hue(hue: f64, chroma: f64, min: f64) -> Color {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let to_u8 = |v: f64| ((v + min) * 255.0).round().clamp(0.0, 255.0) as u8;
+    Color::rgb(to_u8(r), to_u8(g), to_u8(b))
+}
+
+fn clamp01(v: f64) -> f64 {
+    // NaN counts as 0, clamp would keep it
+    if v.is_nan() {
+        0.0
+    } else {
+        v.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+impl From<Color> for Hsl {
+    fn from(c: Color) -> Hsl {
+        let (h, max, min) = hue_max_min(&c);
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl { h, s, l }
+    }
+}
+
+// opaque, out of range saturation and lightness are clamped
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Color {
+        let (s, l) = (clamp01(hsl.s), clamp01(hsl.l));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_hue(hsl.h, chroma, l - chroma / 2.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+impl From<Color> for Hsv {
+    fn from(c: Color) -> Hsv {
+        let (h, max, min) = hue_max_min(&c);
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv { h, s, v: max }
+    }
+}
+
+// opaque, out of range saturation and value are clamped
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Color {
+        let (s, v) = (clamp01(hsv.s), clamp01(hsv.v));
+        let chroma = v * s;
+        from_hue(hsv.h, chroma, v - chroma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn parses_and_formats_hex() {
+        assert_eq!("#ff8000".parse(), Ok(Color::rgb(255, 128, 0)));
+        assert_eq!("#F80".parse(), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!("#ff800080".parse(), Ok(Color::rgba(255, 128, 0, 128)));
+        assert_eq!(Color::rgb(255, 128, 0).to_string(), "#ff8000");
+        assert_eq!(Color::rgba(1, 2, 3, 4).to_hex(), "#01020304");
+
+        for bad in &["ff8000", "#ff800", "#ff80000", "#gg8000", "#ﬀ8000"] {
+            assert!(matches!(
+                Color::parse_hex(bad),
+                Err(ColorError::InvalidHex(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_css() {
+        assert_eq!(" rgb(255, 128,0) ".parse(), Ok(Color::rgb(255, 128, 0)));
+        assert_eq!("rgba(0, 0, 0, 0.5)".parse(), Ok(Color::rgba(0, 0, 0, 128)));
+        assert_eq!(Color::rgb(1, 2, 3).to_css(), "rgb(1, 2, 3)");
+        assert_eq!(Color::rgba(1, 2, 3, 51).to_css(), "rgba(1, 2, 3, 0.2)");
+        assert_eq!(Color::rgba(1, 2, 3, 255).to_css(), "rgba(1, 2, 3, 1)");
+
+        assert_eq!(
+            "rgb(256, 0, 0)".parse::<Color>(),
+            Err(ColorError::ChannelOutOfRange(256))
+        );
+        for bad in &[
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4)",
+            "rgba(1, 2, 3, 2)",
+            "rgb(1, 2, 3",
+        ] {
+            assert!(matches!(
+                bad.parse::<Color>(),
+                Err(ColorError::InvalidRgb(_))
+            ));
+        }
+        assert!(matches!(
+            "red".parse::<Color>(),
+            Err(ColorError::Unrecognized(_))
+        ));
+    }
+
+    #[test]
+    fn from_the_tuple_struct() {
+        assert_eq!(Color::try_from((0, 0, 0)), Ok(Color::BLACK));
+        assert_eq!(
+            Color::try_from((0, -1, 0)),
+            Err(ColorError::ChannelOutOfRange(-1))
+        );
+        assert_eq!(<(i32, i32, i32)>::from(Color::WHITE), (255, 255, 255));
+    }
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        let orange = Color::rgb(255, 128, 0);
+        let hsl = orange.to_hsl();
+        assert!(close(hsl.h, 30.118) && close(hsl.s, 1.0) && close(hsl.l, 0.5));
+        let hsv = orange.to_hsv();
+        assert!(close(hsv.h, 30.118) && close(hsv.s, 1.0) && close(hsv.v, 1.0));
+
+        for &(r, g, b) in &[
+            (0, 0, 0),
+            (255, 255, 255),
+            (12, 200, 99),
+            (90, 10, 250),
+            (128, 128, 128),
+        ] {
+            let c = Color::rgb(r, g, b);
+            assert_eq!(Color::from(c.to_hsl()), c);
+            assert_eq!(Color::from(c.to_hsv()), c);
+        }
+        assert_eq!(
+            Color::from(Hsl {
+                h: 240.0,
+                s: 1.0,
+                l: 0.5
+            }),
+            Color::rgb(0, 0, 255)
+        );
+        assert_eq!(
+            Color::from(Hsv {
+                h: -240.0,
+                s: 1.0,
+                v: 1.0
+            }),
+            Color::rgb(0, 255, 0)
+        );
+    }
+
+    #[test]
+    fn mixes_and_composites() {
+        assert_eq!(
+            Color::BLACK.mix(&Color::WHITE, 0.5),
+            Color::rgb(128, 128, 128)
+        );
+        assert_eq!(Color::BLACK.mix(&Color::WHITE, 2.0), Color::WHITE);
+
+        let half_red = Color::rgba(255, 0, 0, 128);
+        assert_eq!(half_red.over(&Color::WHITE), Color::rgb(255, 127, 127));
+        assert_eq!(
+            Color::rgb(0, 0, 255).over(&Color::WHITE),
+            Color::rgb(0, 0, 255)
+        );
+        assert_eq!(
+            half_red.over(&Color::rgba(0, 0, 255, 128)),
+            Color::rgba(170, 0, 85, 192)
+        );
+    }
+
+    #[test]
+    fn wcag_contrast() {
+        assert!(close(Color::BLACK.contrast_ratio(&Color::WHITE), 21.0));
+        assert!(close(Color::WHITE.contrast_ratio(&Color::WHITE), 1.0));
+        // the classic #777 on white just misses AA
+        let grey: Color = "#777".parse().unwrap();
+        assert!(close(grey.contrast_ratio(&Color::WHITE), 4.478));
+        assert!(!grey.meets_aa(&Color::WHITE, false));
+        assert!(grey.meets_aa(&Color::WHITE, true));
+    }
+}
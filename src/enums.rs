@@ -15,6 +15,7 @@ pub fn enum_values() {
     let six = IpAddrKind::V6;
 
     // can take four or six
+    // net::routing::RoutingTable is what it would look the route up in
     fn route(ip_kind: IpAddrKind) {}
 
     // Advantage: only needs to store the type/kind, and not the data
@@ -50,6 +51,7 @@ pub fn concise_values() {
 }
 
 // Advantage: each variant can have different types and amounts of associated data
+// net::IpAddr is the full version, with parsing and validation
 pub fn multiple_components() {
     enum IpAddr {
         // V4 will always have 4 numerical components (0 - 255)
@@ -64,6 +66,7 @@ pub fn multiple_components() {
 
 // compare enum vs structs alone
 // enums allow for conciseness, organization, and readability
+// messages::Message is this one for real, with a binary encoding
 pub fn compared_to_struct() {
     enum Message {
         Quit,
@@ -86,6 +89,7 @@ pub fn compared_to_struct() {
     // but harder to define a function to take any of these messages
 
     // Define methods on enum with impl
+    // (messages::dispatch::Dispatcher is a call that does something)
     impl Message {
         fn call(&self) {}
     }
@@ -130,11 +134,13 @@ pub fn null_example() {
     // Option<T> enum has a large number of methods to check
 
     // let sum = x + y;
+    // checked::CheckedOption does it: y.checked_add(x) is Some(10)
 }
 
 /// Match
 // analogy: coin-sorting machine -> coin slides into the first hole
 // that fits
+// coins::Coin is this one with change making and a vending machine
 pub fn value_in_cents_example() {
     enum Coin {
         Penny,
@@ -161,6 +167,7 @@ pub fn value_in_cents_example() {
 // match arms can bind to part of the value that match the pattern
 // to extract enum variants
 
+// coins::quarters::UsState has all of them, for collecting
 pub fn pattern_bind() {
     #[derive(Debug)]
     enum UsState {
@@ -191,6 +198,7 @@ pub fn pattern_bind() {
     let coin1 = value_in_cents(Coin::Quarter(UsState::Alaska));
 }
 
+// checked.rs has this for every integer type and for Result too
 pub fn option_example() {
     // if there's a value: add 1 to the value
     // no value -> then return None value and will not attempt to perform any operations
@@ -216,6 +224,7 @@ pub fn option_example() {
 // i.e. always handles the None case by needing to explicitly check it
 // in compile time
 
+// rules::RuleTable is this match built at runtime, with a coverage check
 pub fn placeholder() {
     let some_u8_value = 0u8;
     match some_u8_value {
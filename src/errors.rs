@@ -67,115 +67,9 @@ pub fn panic_example() {
     v[99];
 }
 
-pub mod recoverable {
-    pub fn open_file() {
-        use std::fs::File;
-
-        let f = File::open("hello.txt");
-
-        let f = match f {
-            Ok(file) => file,
-            Err(error) => panic!("Problem opening the file: {:?}", error),
-        };
-    }
-
-    pub fn handling() {
-        use std::fs::File;
-        use std::io::ErrorKind;
-
-        let f = File::open("hello.txt");
-
-        let f = match f {
-            Ok(file) => file,
-            Err(error) => match error.kind() {
-                ErrorKind::NotFound => match File::create("hello.txt") {
-                    Ok(fc) => fc,
-                    Err(e) => panic!("problem creating the file: {:?}", e),
-                },
-                other_error => {
-                    panic!("Problem opening the file {:?}", other_error)
-                }
-            },
-        };
-    }
-
-    // same as above, no (primitive) match statements, easier to read
-    pub fn handling_closures() {
-        use std::fs::File;
-        use std::io::ErrorKind;
-        let f = File::open("hello.txt").unwrap_or_else(|error| {
-            if error.kind() == ErrorKind::NotFound {
-                File::create("hello.txt").unwrap_or_else(|error| {
-                    panic!("problem creating the file: {:?}", error);
-                })
-            } else {
-                panic!("Problem opening the file {:?}", error);
-            }
-        });
-    }
-
-    // shortcuts for panic! `unwrap` and `expect`
-    pub fn unwrap_example() {
-        use std::fs::File;
-
-        let f = File::open("hello.txt").unwrap();
-    }
-
-    pub fn expect_example() {
-        use std::fs::File;
-
-        let f = File::open("hello.txt").expect("failed to open hello.txt");
-    }
-
-    pub fn propagating() {
-        use std::fs::File;
-        use std::io::{self, read};
-
-        fn read_username_from_file() -> Result<String, io: Error> {
-            let f = File::open("Hello.txt");
-
-            let mut f = match f {
-                Ok(file) => file,
-                Err(e) => return Err(e),
-            };
-
-            let mut s = String::new();
-
-            // returns an error that also might fail
-            match f.read_to_string(&mut s) {
-                Ok(_) => Ok(s),
-                Err(e) => Err(e),
-            }
-        }
-
-        // using the ? Operator with the same functionality
-        fn read_username_from_file_operator() -> Result<String, io::Error> {
-            use std::fs::File;
-            use std::io::{self, read};
-            let mut f = File::open("hello.txt")?;
-            let mut s = String::new();
-            f.read_to_string(&mut s)?;
-            Ok(s);
-        }
-
-        fn shorter() {
-            let mut s = String::new();
-
-            File::open("hello.txt")?.read_to_string(&mut s)?;
-
-            Ok(s)
-        }
-
-        fn shortest() -> Result<String, io::Error> {
-            use std::fs;
-            use std::io;
-
-            // opens the file, creates new string, reads the file, put content into
-            // string, and returns it
-            fs::read_to_string("hello.txt")
-        }
-    }
-}
+// recoverable: the examples returning an AppError instead of panicking,
+// and `propagating` adding context to each step, are in the library,
+// compiled and tested, in src/app_error/recoverable.rs
 
 pub mod panic_or_not {
     pub fn guess_number_old() {
@@ -175,6 +175,7 @@ Method
 - impl<T> tells compiler that generic type is used
 - or can specify point
  */
+// geometry::point::Point is the full version, with arithmetic and distance
 pub mod method_def {
     pub fn example() {
         struct Point<T> {
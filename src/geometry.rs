@@ -0,0 +1,268 @@
+// Geometry
+/*
+One Rectangle for layout code, grown out of the structs chapter examples
+(`structs::rectangles` .. `structs::associated_functions`)
+
+- positioned: top-left corner at (x, y), y grows downwards
+- edges are half-open: a rectangle covers x..x + width, y..y + height
+  so rectangles that only touch do not overlap
+- width and height are u32, positions are i32
+  edges are computed in i64 so they never overflow
+ */
+// approximate equality for the float types, in src/geometry/approx.rs
+pub mod approx;
+// 3x3 and 4x4 transforms, in src/geometry/matrix.rs
+pub mod matrix;
+// packing many rectangles into containers, in src/geometry/packing.rs
+pub mod packing;
+// generic Point<T>, in src/geometry/point.rs
+pub mod point;
+// spatial index for hit-testing, in src/geometry/quadtree.rs
+pub mod quadtree;
+// 3D vector, in src/geometry/vector.rs
+pub mod vector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rectangle {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rectangle {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+        Rectangle {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    // at the origin, for when only the size matters
+    pub fn with_size(width: u32, height: u32) -> Rectangle {
+        Rectangle::new(0, 0, width, height)
+    }
+
+    pub fn square(size: u32) -> Rectangle {
+        Rectangle::with_size(size, size)
+    }
+
+    /// Builds the rectangle spanning two edges, `None` if it doesn't fit
+    /// the field types
+    pub fn from_edges(left: i64, top: i64, right: i64, bottom: i64) -> Option<Rectangle> {
+        use std::convert::TryFrom;
+
+        if right < left || bottom < top {
+            return None;
+        }
+        Some(Rectangle {
+            x: i32::try_from(left).ok()?,
+            y: i32::try_from(top).ok()?,
+            width: u32::try_from(right - left).ok()?,
+            height: u32::try_from(bottom - top).ok()?,
+        })
+    }
+
+    pub fn left(&self) -> i64 {
+        i64::from(self.x)
+    }
+
+    pub fn top(&self) -> i64 {
+        i64::from(self.y)
+    }
+
+    pub fn right(&self) -> i64 {
+        self.left() + i64::from(self.width)
+    }
+
+    pub fn bottom(&self) -> i64 {
+        self.top() + i64::from(self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // u32 * u32 always fits in a u64
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    // `None` instead of wrapping when the area doesn't fit a u32
+    pub fn checked_area(&self) -> Option<u32> {
+        self.width.checked_mul(self.height)
+    }
+
+    // swaps width and height in place
+    pub fn rotated(&self) -> Rectangle {
+        Rectangle {
+            width: self.height,
+            height: self.width,
+            ..*self
+        }
+    }
+
+    pub fn translated(&self, dx: i32, dy: i32) -> Option<Rectangle> {
+        Some(Rectangle {
+            x: self.x.checked_add(dx)?,
+            y: self.y.checked_add(dy)?,
+            ..*self
+        })
+    }
+
+    pub fn contains_point(&self, x: i64, y: i64) -> bool {
+        self.left() <= x && x < self.right() && self.top() <= y && y < self.bottom()
+    }
+
+    /// `other` lies entirely inside `self`, edges may touch
+    pub fn contains(&self, other: &Rectangle) -> bool {
+        self.left() <= other.left()
+            && other.right() <= self.right()
+            && self.top() <= other.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Share some area, touching edges don't count
+    pub fn overlaps(&self, other: &Rectangle) -> bool {
+        self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    pub fn intersection(&self, other: &Rectangle) -> Option<Rectangle> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Rectangle::from_edges(
+            self.left().max(other.left()),
+            self.top().max(other.top()),
+            self.right().min(other.right()),
+            self.bottom().min(other.bottom()),
+        )
+    }
+
+    /// Smallest rectangle covering both, `None` if that is too wide or tall
+    /// for a u32
+    pub fn union(&self, other: &Rectangle) -> Option<Rectangle> {
+        Rectangle::from_edges(
+            self.left().min(other.left()),
+            self.top().min(other.top()),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    /// Scales position and size away from the origin, rounding to the
+    /// nearest pixel; `None` for negative or non-finite factors and results
+    /// that don't fit
+    pub fn scale(&self, factor: f64) -> Option<Rectangle> {
+        if !factor.is_finite() || factor < 0.0 {
+            return None;
+        }
+        let left = (self.left() as f64 * factor).round();
+        let top = (self.top() as f64 * factor).round();
+        let width = (f64::from(self.width) * factor).round();
+        let height = (f64::from(self.height) * factor).round();
+        // float -> int casts saturate, so check the range first
+        let fits_i32 = |v: f64| v >= f64::from(i32::MIN) && v <= f64::from(i32::MAX);
+        let fits_u32 = |v: f64| v <= f64::from(u32::MAX);
+        if !(fits_i32(left) && fits_i32(top) && fits_u32(width) && fits_u32(height)) {
+            return None;
+        }
+        Some(Rectangle::new(
+            left as i32,
+            top as i32,
+            width as u32,
+            height as u32,
+        ))
+    }
+
+    /// Whether `other` fits inside as is, ignoring position
+    pub fn can_hold_upright(&self, other: &Rectangle) -> bool {
+        self.width >= other.width && self.height >= other.height
+    }
+
+    /// Whether `other` fits inside, turned 90 degrees if needed, ignoring
+    /// position
+    pub fn can_hold(&self, other: &Rectangle) -> bool {
+        self.can_hold_upright(other) || self.can_hold_upright(&other.rotated())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges_are_half_open() {
+        let r = Rectangle::new(0, 0, 10, 5);
+        assert!(r.contains_point(0, 0));
+        assert!(r.contains_point(9, 4));
+        assert!(!r.contains_point(10, 4));
+        assert!(!r.contains_point(9, 5));
+
+        let touching = Rectangle::new(10, 0, 10, 5);
+        assert!(!r.overlaps(&touching));
+        assert_eq!(r.intersection(&touching), None);
+    }
+
+    #[test]
+    fn intersection_and_union() {
+        let a = Rectangle::new(0, 0, 10, 10);
+        let b = Rectangle::new(5, -5, 10, 10);
+        assert!(a.overlaps(&b));
+        assert_eq!(a.intersection(&b), Some(Rectangle::new(5, 0, 5, 5)));
+        assert_eq!(a.union(&b), Some(Rectangle::new(0, -5, 15, 15)));
+    }
+
+    #[test]
+    fn union_too_big_for_u32() {
+        let a = Rectangle::new(i32::MIN, 0, 1, 1);
+        let b = Rectangle::new(i32::MAX, 0, u32::MAX, 1);
+        assert_eq!(a.union(&b), None);
+    }
+
+    #[test]
+    fn contains_rectangle() {
+        let outer = Rectangle::new(0, 0, 10, 10);
+        assert!(outer.contains(&Rectangle::new(0, 0, 10, 10)));
+        assert!(outer.contains(&Rectangle::new(2, 2, 3, 3)));
+        assert!(!outer.contains(&Rectangle::new(8, 8, 3, 3)));
+    }
+
+    #[test]
+    fn area_never_overflows() {
+        let huge = Rectangle::with_size(u32::MAX, 2);
+        assert_eq!(huge.area(), u64::from(u32::MAX) * 2);
+        assert_eq!(huge.checked_area(), None);
+        assert_eq!(Rectangle::with_size(30, 50).checked_area(), Some(1500));
+    }
+
+    #[test]
+    fn scale_moves_and_resizes() {
+        let r = Rectangle::new(-2, 4, 3, 5);
+        assert_eq!(r.scale(2.0), Some(Rectangle::new(-4, 8, 6, 10)));
+        assert_eq!(r.scale(0.5), Some(Rectangle::new(-1, 2, 2, 3)));
+        assert_eq!(r.scale(-1.0), None);
+        assert_eq!(r.scale(f64::NAN), None);
+        assert_eq!(Rectangle::with_size(u32::MAX, 1).scale(2.0), None);
+    }
+
+    // the structs chapter examples, with rotation
+    #[test]
+    fn can_hold_with_rotation() {
+        let rect1 = Rectangle::with_size(30, 50);
+        let rect2 = Rectangle::with_size(10, 40);
+        let rect3 = Rectangle::with_size(60, 45);
+        assert!(rect1.can_hold(&rect2));
+        assert!(!rect1.can_hold(&rect3));
+
+        let lying = Rectangle::with_size(50, 30);
+        assert!(!rect1.can_hold_upright(&lying));
+        assert!(rect1.can_hold(&lying));
+        assert!(rect1.can_hold(&rect1));
+    }
+}
@@ -0,0 +1,81 @@
+// Approximate equality
+/*
+Floats that went through a few multiplications are rarely exactly equal,
+so the 3D types compare with a tolerance instead
+
+- two numbers are close when they differ by at most epsilon, scaled up by
+  the larger of them once that is above 1, so it works for big values too
+- vectors and matrices are close when every component is
+- assert_approx_eq!(left, right) or assert_approx_eq!(left, right, epsilon)
+  panics like assert_eq! otherwise, for use in tests
+ */
+
+// default tolerance for assert_approx_eq!
+pub const EPSILON: f64 = 1e-9;
+
+pub trait ApproxEq {
+    fn approx_eq(&self, other: &Self, epsilon: f64) -> bool;
+}
+
+impl ApproxEq for f64 {
+    fn approx_eq(&self, other: &f64, epsilon: f64) -> bool {
+        if self == other {
+            // also equal infinities
+            return true;
+        }
+        let scale = self.abs().max(other.abs()).max(1.0);
+        (self - other).abs() <= epsilon * scale
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for [T] {
+    fn approx_eq(&self, other: &[T], epsilon: f64) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.approx_eq(b, epsilon))
+    }
+}
+
+impl<T: ApproxEq, const N: usize> ApproxEq for [T; N] {
+    fn approx_eq(&self, other: &[T; N], epsilon: f64) -> bool {
+        self[..].approx_eq(&other[..], epsilon)
+    }
+}
+
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, $crate::geometry::approx::EPSILON)
+    };
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {
+        match (&$left, &$right, $epsilon) {
+            (left, right, epsilon) => {
+                if !$crate::geometry::approx::ApproxEq::approx_eq(left, right, epsilon) {
+                    panic!(
+                        "assertion failed: `(left ≈ right)` (epsilon {:?})\n  left: `{:?}`\n right: `{:?}`",
+                        epsilon, left, right
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_with_the_values() {
+        assert!(0.1_f64.approx_eq(&(0.3 - 0.2), EPSILON));
+        assert!(!1.0_f64.approx_eq(&1.001, EPSILON));
+        assert!(1e12_f64.approx_eq(&(1e12 + 1e-4), EPSILON));
+        assert!(f64::INFINITY.approx_eq(&f64::INFINITY, EPSILON));
+        assert!(!f64::NAN.approx_eq(&f64::NAN, EPSILON));
+        assert!([1.0, 2.0].approx_eq(&[1.0, 2.0 + 1e-12], EPSILON));
+    }
+
+    #[test]
+    #[should_panic(expected = "left ≈ right")]
+    fn macro_panics_when_apart() {
+        crate::assert_approx_eq!(1.0, 1.1, 0.01);
+    }
+}
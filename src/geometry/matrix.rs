@@ -0,0 +1,363 @@
+// Matrices
+/*
+3x3 and 4x4 transforms for Vec3
+
+- row-major storage, `m[row][column]`, applied to column vectors: M * v
+- combining: (A * B) * v is B first, then A
+- rotations take radians and turn counter-clockwise when looking down the
+  axis towards the origin (right-handed)
+- Mat3 rotates and scales; Mat4 adds translation, so it tells points
+  (moved by it) from directions (not moved)
+ */
+use std::ops::Mul;
+
+use super::approx::ApproxEq;
+use super::vector::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3 {
+    pub m: [[f64; 3]; 3],
+}
+
+impl Mat3 {
+    pub const IDENTITY: Mat3 = Mat3 {
+        m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    pub const fn new(m: [[f64; 3]; 3]) -> Mat3 {
+        Mat3 { m }
+    }
+
+    pub fn scale(factors: Vec3) -> Mat3 {
+        Mat3::new([
+            [factors.x, 0.0, 0.0],
+            [0.0, factors.y, 0.0],
+            [0.0, 0.0, factors.z],
+        ])
+    }
+
+    pub fn rotation_x(angle: f64) -> Mat3 {
+        let (sin, cos) = angle.sin_cos();
+        Mat3::new([[1.0, 0.0, 0.0], [0.0, cos, -sin], [0.0, sin, cos]])
+    }
+
+    pub fn rotation_y(angle: f64) -> Mat3 {
+        let (sin, cos) = angle.sin_cos();
+        Mat3::new([[cos, 0.0, sin], [0.0, 1.0, 0.0], [-sin, 0.0, cos]])
+    }
+
+    pub fn rotation_z(angle: f64) -> Mat3 {
+        let (sin, cos) = angle.sin_cos();
+        Mat3::new([[cos, -sin, 0.0], [sin, cos, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    /// Rotation around any axis (Rodrigues), `None` for a zero axis
+    pub fn rotation(axis: Vec3, angle: f64) -> Option<Mat3> {
+        let Vec3 { x, y, z } = axis.normalized()?;
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        Some(Mat3::new([
+            [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y],
+            [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x],
+            [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos],
+        ]))
+    }
+
+    pub fn transpose(&self) -> Mat3 {
+        let mut t = [[0.0; 3]; 3];
+        for (r, row) in self.m.iter().enumerate() {
+            for (c, &v) in row.iter().enumerate() {
+                t[c][r] = v;
+            }
+        }
+        Mat3::new(t)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// `None` when the matrix flattens space (determinant 0)
+    pub fn inverse(&self) -> Option<Mat3> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        // transposed cofactors over the determinant
+        let m = &self.m;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        Some(Mat3::new([
+            [
+                cofactor(1, 2, 1, 2) / det,
+                -cofactor(0, 2, 1, 2) / det,
+                cofactor(0, 1, 1, 2) / det,
+            ],
+            [
+                -cofactor(1, 2, 0, 2) / det,
+                cofactor(0, 2, 0, 2) / det,
+                -cofactor(0, 1, 0, 2) / det,
+            ],
+            [
+                cofactor(1, 2, 0, 1) / det,
+                -cofactor(0, 2, 0, 1) / det,
+                cofactor(0, 1, 0, 1) / det,
+            ],
+        ]))
+    }
+}
+
+impl Default for Mat3 {
+    fn default() -> Mat3 {
+        Mat3::IDENTITY
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, v: Vec3) -> Vec3 {
+        let row = |r: [f64; 3]| r[0] * v.x + r[1] * v.y + r[2] * v.z;
+        Vec3::new(row(self.m[0]), row(self.m[1]), row(self.m[2]))
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, other: Mat3) -> Mat3 {
+        let mut out = [[0.0; 3]; 3];
+        for (r, row) in out.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().enumerate() {
+                *v = (0..3).map(|k| self.m[r][k] * other.m[k][c]).sum();
+            }
+        }
+        Mat3::new(out)
+    }
+}
+
+impl ApproxEq for Mat3 {
+    fn approx_eq(&self, other: &Mat3, epsilon: f64) -> bool {
+        self.m.approx_eq(&other.m, epsilon)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub const fn new(m: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY;
+        m.m[0][3] = offset.x;
+        m.m[1][3] = offset.y;
+        m.m[2][3] = offset.z;
+        m
+    }
+
+    pub fn scale(factors: Vec3) -> Mat4 {
+        Mat4::from(Mat3::scale(factors))
+    }
+
+    pub fn rotation_x(angle: f64) -> Mat4 {
+        Mat4::from(Mat3::rotation_x(angle))
+    }
+
+    pub fn rotation_y(angle: f64) -> Mat4 {
+        Mat4::from(Mat3::rotation_y(angle))
+    }
+
+    pub fn rotation_z(angle: f64) -> Mat4 {
+        Mat4::from(Mat3::rotation_z(angle))
+    }
+
+    pub fn rotation(axis: Vec3, angle: f64) -> Option<Mat4> {
+        Mat3::rotation(axis, angle).map(Mat4::from)
+    }
+
+    /// Applies the whole transform, translation included
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let [x, y, z, w] = self.apply([p.x, p.y, p.z, 1.0]);
+        // only projections change w
+        if w == 1.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x / w, y / w, z / w)
+        }
+    }
+
+    /// Applies rotation and scale only, a direction has no position
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let [x, y, z, _] = self.apply([v.x, v.y, v.z, 0.0]);
+        Vec3::new(x, y, z)
+    }
+
+    fn apply(&self, v: [f64; 4]) -> [f64; 4] {
+        let mut out = [0.0; 4];
+        for (o, row) in out.iter_mut().zip(&self.m) {
+            *o = row.iter().zip(&v).map(|(a, b)| a * b).sum();
+        }
+        out
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut t = [[0.0; 4]; 4];
+        for (r, row) in self.m.iter().enumerate() {
+            for (c, &v) in row.iter().enumerate() {
+                t[c][r] = v;
+            }
+        }
+        Mat4::new(t)
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting, `None` when the
+    /// matrix is singular
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::IDENTITY.m;
+        for col in 0..4 {
+            // largest pivot keeps the rounding errors down
+            let pivot = (col..4).max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))?;
+            if a[pivot][col] == 0.0 || !a[pivot][col].is_finite() {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let p = a[col][col];
+            for c in 0..4 {
+                a[col][c] /= p;
+                inv[col][c] /= p;
+            }
+            for r in 0..4 {
+                if r == col {
+                    continue;
+                }
+                let factor = a[r][col];
+                for c in 0..4 {
+                    a[r][c] -= factor * a[col][c];
+                    inv[r][c] -= factor * inv[col][c];
+                }
+            }
+        }
+        Some(Mat4::new(inv))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Mat4 {
+        Mat4::IDENTITY
+    }
+}
+
+// the 3x3 in the top left, no translation
+impl From<Mat3> for Mat4 {
+    fn from(m3: Mat3) -> Mat4 {
+        let mut m = Mat4::IDENTITY;
+        for (row, row3) in m.m.iter_mut().zip(&m3.m) {
+            row[..3].copy_from_slice(row3);
+        }
+        m
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut out = [[0.0; 4]; 4];
+        for (r, row) in out.iter_mut().enumerate() {
+            for (c, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| self.m[r][k] * other.m[k][c]).sum();
+            }
+        }
+        Mat4::new(out)
+    }
+}
+
+impl ApproxEq for Mat4 {
+    fn approx_eq(&self, other: &Mat4, epsilon: f64) -> bool {
+        self.m.approx_eq(&other.m, epsilon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_approx_eq;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn rotations_turn_counter_clockwise() {
+        assert_approx_eq!(Mat3::rotation_z(FRAC_PI_2) * Vec3::X, Vec3::Y);
+        assert_approx_eq!(Mat3::rotation_x(FRAC_PI_2) * Vec3::Y, Vec3::Z);
+        assert_approx_eq!(Mat3::rotation_y(FRAC_PI_2) * Vec3::Z, Vec3::X);
+
+        // the axis-angle form agrees with the fixed-axis ones
+        let axis = Mat3::rotation(Vec3::new(0.0, 0.0, 2.0), 0.7).unwrap();
+        assert_approx_eq!(axis, Mat3::rotation_z(0.7));
+        assert_eq!(Mat3::rotation(Vec3::ZERO, 1.0), None);
+
+        // a third of a turn around the diagonal cycles the axes
+        let diagonal = Mat3::rotation(Vec3::new(1.0, 1.0, 1.0), 2.0 * PI / 3.0).unwrap();
+        assert_approx_eq!(diagonal * Vec3::X, Vec3::Y);
+        assert_approx_eq!(diagonal * Vec3::Y, Vec3::Z);
+    }
+
+    #[test]
+    fn mat3_inverse() {
+        let m = Mat3::rotation_x(0.3) * Mat3::scale(Vec3::new(2.0, 3.0, 4.0));
+        assert_approx_eq!(m.determinant(), 24.0);
+        assert_approx_eq!(m * m.inverse().unwrap(), Mat3::IDENTITY);
+        // a rotation's inverse is its transpose
+        let r = Mat3::rotation_y(1.1);
+        assert_approx_eq!(r.inverse().unwrap(), r.transpose());
+        assert_eq!(Mat3::scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+    }
+
+    #[test]
+    fn mat4_transforms_points_and_vectors() {
+        // scale, then rotate, then move
+        let m = Mat4::translation(Vec3::new(10.0, 0.0, 0.0))
+            * Mat4::rotation_z(FRAC_PI_2)
+            * Mat4::scale(Vec3::new(2.0, 2.0, 2.0));
+        assert_approx_eq!(m.transform_point(Vec3::X), Vec3::new(10.0, 2.0, 0.0));
+        assert_approx_eq!(m.transform_vector(Vec3::X), Vec3::new(0.0, 2.0, 0.0));
+
+        let back = m.inverse().unwrap();
+        assert_approx_eq!(back * m, Mat4::IDENTITY);
+        assert_approx_eq!(back.transform_point(Vec3::new(10.0, 2.0, 0.0)), Vec3::X);
+        assert_approx_eq!(m.transpose().transpose(), m);
+        assert_eq!(Mat4::scale(Vec3::ZERO).inverse(), None);
+    }
+
+    #[test]
+    fn inverse_needs_pivoting() {
+        // a zero on the diagonal, fine once the rows are swapped
+        let m = Mat4::new([
+            [0.0, 1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 3.0, 0.0],
+        ]);
+        assert_approx_eq!(m * m.inverse().unwrap(), Mat4::IDENTITY);
+    }
+}
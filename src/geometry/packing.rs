@@ -0,0 +1,500 @@
+// Packing
+/*
+Places many rectangles into one container, or into as few fixed-size bins
+as it can manage. Exact bin packing is NP-hard, these are the usual
+heuristics:
+
+- items go in largest first (longest side, then area)
+- Guillotine keeps a list of free rectangles; an item takes the free one
+  it fits most tightly and the rest is cut in two along the shorter side
+- Skyline keeps the top outline of what's been placed; an item goes where
+  its top edge ends lowest, leftmost on ties
+
+Both only ever hand out space nobody else has, so placements never overlap.
+ */
+use super::Rectangle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Guillotine,
+    Skyline,
+}
+
+/// Where one of the input rectangles ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    // position of the rectangle in the input slice
+    pub index: usize,
+    pub bin: usize,
+    pub rect: Rectangle,
+    // placed turned 90 degrees
+    pub rotated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packing {
+    // the container, or the shape of every bin
+    pub bin: Rectangle,
+    // bins in use
+    pub bins: usize,
+    // in the order they were placed
+    pub placements: Vec<Placement>,
+    // input indices that didn't fit, in input order
+    pub unplaced: Vec<usize>,
+}
+
+impl Packing {
+    pub fn in_bin(&self, bin: usize) -> impl Iterator<Item = &Placement> {
+        self.placements.iter().filter(move |p| p.bin == bin)
+    }
+
+    pub fn used_area(&self) -> u64 {
+        self.placements.iter().map(|p| p.rect.area()).sum()
+    }
+
+    /// Share of the area of all bins in use that is covered, 0.0 to 1.0
+    pub fn utilization(&self) -> f64 {
+        let total = self.bin.area() as f64 * self.bins as f64;
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.used_area() as f64 / total
+    }
+
+    pub fn bin_utilization(&self, bin: usize) -> f64 {
+        if bin >= self.bins || self.bin.is_empty() {
+            return 0.0;
+        }
+        let used: u64 = self.in_bin(bin).map(|p| p.rect.area()).sum();
+        used as f64 / self.bin.area() as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packer {
+    strategy: Strategy,
+    rotation: bool,
+}
+
+impl Packer {
+    // rotation is off until asked for
+    pub fn new(strategy: Strategy) -> Packer {
+        Packer {
+            strategy,
+            rotation: false,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: bool) -> Packer {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Packs `items` into `container`; whatever doesn't fit is left in
+    /// `unplaced`
+    ///
+    /// Placements are in the container's coordinates. Empty rectangles
+    /// have nothing to place and are always reported unplaced.
+    pub fn pack(&self, container: Rectangle, items: &[Rectangle]) -> Packing {
+        self.run(container, items, false)
+    }
+
+    /// Packs `items` into as few bins shaped like `bin` as it can, opening
+    /// a new one when an item fits none of the open bins
+    ///
+    /// Every bin uses `bin`'s coordinates, `Placement::bin` tells them
+    /// apart. Only items too big for an empty bin are left unplaced.
+    pub fn pack_bins(&self, bin: Rectangle, items: &[Rectangle]) -> Packing {
+        self.run(bin, items, true)
+    }
+
+    fn run(&self, bin: Rectangle, items: &[Rectangle], open_bins: bool) -> Packing {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by_key(|&i| {
+            let r = &items[i];
+            (
+                std::cmp::Reverse(r.width.max(r.height)),
+                std::cmp::Reverse(r.area()),
+                i,
+            )
+        });
+
+        let mut spaces = Vec::new();
+        if !open_bins {
+            spaces.push(Space::new(self.strategy, bin));
+        }
+        let mut placements = Vec::with_capacity(items.len());
+        let mut unplaced = Vec::new();
+
+        for index in order {
+            let item = &items[index];
+            let fits = if self.rotation {
+                bin.can_hold(item)
+            } else {
+                bin.can_hold_upright(item)
+            };
+            if item.is_empty() || !fits {
+                unplaced.push(index);
+                continue;
+            }
+
+            let mut placed = spaces
+                .iter_mut()
+                .enumerate()
+                .find_map(|(n, space)| space.insert(item, self.rotation).map(|p| (n, p)));
+            if placed.is_none() && open_bins {
+                let mut space = Space::new(self.strategy, bin);
+                // an empty bin that can hold the item only fails when the
+                // spot is outside what a Rectangle can represent
+                if let Some(p) = space.insert(item, self.rotation) {
+                    spaces.push(space);
+                    placed = Some((spaces.len() - 1, p));
+                }
+            }
+            match placed {
+                Some((bin, (rect, rotated))) => placements.push(Placement {
+                    index,
+                    bin,
+                    rect,
+                    rotated,
+                }),
+                None => unplaced.push(index),
+            }
+        }
+
+        unplaced.sort_unstable();
+        Packing {
+            bin,
+            bins: spaces.len(),
+            placements,
+            unplaced,
+        }
+    }
+}
+
+// the item as is, then turned if allowed and it makes a difference
+fn orientations(item: &Rectangle, rotation: bool) -> Vec<(u32, u32, bool)> {
+    let mut sizes = vec![(item.width, item.height, false)];
+    if rotation && item.width != item.height {
+        sizes.push((item.height, item.width, true));
+    }
+    sizes
+}
+
+/// A run of the skyline: the outline is at `y` from `x` to `x + width`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    x: i64,
+    y: i64,
+    width: i64,
+}
+
+/// Free space left in one bin
+#[derive(Debug, Clone)]
+enum Space {
+    // disjoint free rectangles
+    Guillotine(Vec<Rectangle>),
+    // segments left to right, covering the bin's whole width
+    Skyline {
+        bounds: Rectangle,
+        segments: Vec<Segment>,
+    },
+}
+
+impl Space {
+    fn new(strategy: Strategy, bin: Rectangle) -> Space {
+        match strategy {
+            Strategy::Guillotine => Space::Guillotine(vec![bin]),
+            Strategy::Skyline => Space::Skyline {
+                bounds: bin,
+                segments: vec![Segment {
+                    x: bin.left(),
+                    y: bin.top(),
+                    width: i64::from(bin.width),
+                }],
+            },
+        }
+    }
+
+    fn insert(&mut self, item: &Rectangle, rotation: bool) -> Option<(Rectangle, bool)> {
+        match self {
+            Space::Guillotine(free) => guillotine_insert(free, item, rotation),
+            Space::Skyline { bounds, segments } => skyline_insert(bounds, segments, item, rotation),
+        }
+    }
+}
+
+fn guillotine_insert(
+    free: &mut Vec<Rectangle>,
+    item: &Rectangle,
+    rotation: bool,
+) -> Option<(Rectangle, bool)> {
+    // tightest fit: least area left over, then shortest side left over
+    let (i, (w, h, rotated)) = free
+        .iter()
+        .enumerate()
+        .flat_map(|(i, space)| {
+            orientations(item, rotation)
+                .into_iter()
+                .map(move |size| (i, space, size))
+        })
+        .filter(|(_, space, (w, h, _))| space.width >= *w && space.height >= *h)
+        .min_by_key(|(_, space, (w, h, _))| {
+            let waste = space.area() - u64::from(*w) * u64::from(*h);
+            (waste, (space.width - w).min(space.height - h))
+        })
+        .map(|(i, _, size)| (i, size))?;
+    let space = free.swap_remove(i);
+    let placed = Rectangle::new(space.x, space.y, w, h);
+
+    // cut along the shorter leftover so the bigger piece stays whole
+    let (right, below) = if space.width - w < space.height - h {
+        (
+            (placed.right(), space.top(), space.right(), placed.bottom()),
+            (space.left(), placed.bottom(), space.right(), space.bottom()),
+        )
+    } else {
+        (
+            (placed.right(), space.top(), space.right(), space.bottom()),
+            (
+                space.left(),
+                placed.bottom(),
+                placed.right(),
+                space.bottom(),
+            ),
+        )
+    };
+    // pieces starting past i32::MAX can't be represented, nor used
+    for &(left, top, right, bottom) in &[right, below] {
+        if let Some(piece) = Rectangle::from_edges(left, top, right, bottom) {
+            if !piece.is_empty() {
+                free.push(piece);
+            }
+        }
+    }
+    Some((placed, rotated))
+}
+
+fn skyline_insert(
+    bounds: &Rectangle,
+    segments: &mut Vec<Segment>,
+    item: &Rectangle,
+    rotation: bool,
+) -> Option<(Rectangle, bool)> {
+    // lowest top edge, then leftmost
+    let (i, rect, rotated) = (0..segments.len())
+        .flat_map(|i| {
+            orientations(item, rotation)
+                .into_iter()
+                .map(move |size| (i, size))
+        })
+        .filter_map(|(i, (w, h, rotated))| {
+            let x = segments[i].x;
+            let (w, h) = (i64::from(w), i64::from(h));
+            if x + w > bounds.right() {
+                return None;
+            }
+            // rests on the highest segment under it
+            let y = segments[i..]
+                .iter()
+                .take_while(|s| s.x < x + w)
+                .map(|s| s.y)
+                .max()?;
+            if y + h > bounds.bottom() {
+                return None;
+            }
+            let rect = Rectangle::from_edges(x, y, x + w, y + h)?;
+            Some((i, rect, rotated))
+        })
+        .min_by_key(|(_, rect, _)| (rect.bottom(), rect.left()))?;
+
+    segments.insert(
+        i,
+        Segment {
+            x: rect.left(),
+            y: rect.bottom(),
+            width: i64::from(rect.width),
+        },
+    );
+    // trim what the new segment now covers
+    let end = rect.right();
+    while let Some(next) = segments.get_mut(i + 1) {
+        if next.x >= end {
+            break;
+        }
+        let covered = end - next.x;
+        if next.width <= covered {
+            segments.remove(i + 1);
+        } else {
+            next.x += covered;
+            next.width -= covered;
+            break;
+        }
+    }
+    // join neighbours at the same height
+    let mut j = 0;
+    while j + 1 < segments.len() {
+        if segments[j].y == segments[j + 1].y {
+            segments[j].width += segments[j + 1].width;
+            segments.remove(j + 1);
+        } else {
+            j += 1;
+        }
+    }
+    Some((rect, rotated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [Strategy; 2] = [Strategy::Guillotine, Strategy::Skyline];
+
+    // every item is placed once or unplaced, at its size, inside the bin
+    // and clear of everything else in the same bin
+    fn check(packing: &Packing, items: &[Rectangle]) {
+        let mut seen = vec![0; items.len()];
+        for p in &packing.placements {
+            seen[p.index] += 1;
+            assert!(p.bin < packing.bins);
+            assert!(packing.bin.contains(&p.rect), "{:?} outside the bin", p);
+            let item = items[p.index];
+            let expected = if p.rotated { item.rotated() } else { item };
+            assert_eq!(
+                (p.rect.width, p.rect.height),
+                (expected.width, expected.height)
+            );
+        }
+        for &i in &packing.unplaced {
+            seen[i] += 1;
+        }
+        assert!(seen.iter().all(|&n| n == 1), "placed or unplaced twice");
+
+        for (n, a) in packing.placements.iter().enumerate() {
+            for b in &packing.placements[n + 1..] {
+                assert!(
+                    a.bin != b.bin || !a.rect.overlaps(&b.rect),
+                    "{:?} overlaps {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    // deterministic assortment of sizes from 1 to max
+    fn assorted(count: usize, max: u32) -> Vec<Rectangle> {
+        let mut seed: u32 = 12345;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) % max + 1
+        };
+        (0..count)
+            .map(|_| Rectangle::with_size(next(), next()))
+            .collect()
+    }
+
+    #[test]
+    fn no_overlaps_in_a_single_container() {
+        let items = assorted(200, 20);
+        let container = Rectangle::new(-50, 30, 100, 80);
+        for &strategy in &STRATEGIES {
+            for &rotation in &[false, true] {
+                let packing = Packer::new(strategy)
+                    .with_rotation(rotation)
+                    .pack(container, &items);
+                check(&packing, &items);
+                assert_eq!(packing.bins, 1);
+                assert!(!packing.placements.is_empty());
+                assert!(!packing.unplaced.is_empty());
+                assert!(packing.utilization() > 0.5, "{:?}", strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn no_overlaps_across_bins() {
+        let items = assorted(100, 30);
+        for &strategy in &STRATEGIES {
+            for &rotation in &[false, true] {
+                let packing = Packer::new(strategy)
+                    .with_rotation(rotation)
+                    .pack_bins(Rectangle::square(64), &items);
+                check(&packing, &items);
+                assert!(packing.unplaced.is_empty());
+                let total: u64 = items.iter().map(Rectangle::area).sum();
+                assert_eq!(packing.used_area(), total);
+                // never more bins than items, never fewer than the area needs
+                assert!(packing.bins <= items.len());
+                assert!(packing.bins as u64 * 64 * 64 >= total);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_fit_fills_the_container() {
+        let items = vec![
+            Rectangle::with_size(6, 4),
+            Rectangle::with_size(4, 4),
+            Rectangle::with_size(10, 6),
+        ];
+        for &strategy in &STRATEGIES {
+            let packing = Packer::new(strategy).pack(Rectangle::square(10), &items);
+            check(&packing, &items);
+            assert!(packing.unplaced.is_empty());
+            assert_eq!(packing.utilization(), 1.0);
+        }
+    }
+
+    #[test]
+    fn rotation_makes_things_fit() {
+        let items = vec![Rectangle::with_size(10, 2), Rectangle::with_size(10, 2)];
+        let container = Rectangle::with_size(4, 10);
+        for &strategy in &STRATEGIES {
+            let upright = Packer::new(strategy).pack(container, &items);
+            assert_eq!(upright.unplaced, vec![0, 1]);
+
+            let turned = Packer::new(strategy)
+                .with_rotation(true)
+                .pack(container, &items);
+            check(&turned, &items);
+            assert!(turned.unplaced.is_empty());
+            assert!(turned.placements.iter().all(|p| p.rotated));
+        }
+    }
+
+    #[test]
+    fn bins_are_opened_only_when_needed() {
+        // two halves per bin, then one that needs a bin of its own
+        let mut items = vec![Rectangle::with_size(5, 10); 4];
+        items.push(Rectangle::square(6));
+        for &strategy in &STRATEGIES {
+            let packing = Packer::new(strategy).pack_bins(Rectangle::square(10), &items);
+            check(&packing, &items);
+            assert_eq!(packing.bins, 3);
+            assert_eq!(packing.bin_utilization(0), 1.0);
+            assert_eq!(packing.bin_utilization(1), 1.0);
+            assert_eq!(packing.bin_utilization(2), 0.36);
+            assert_eq!(packing.in_bin(2).count(), 1);
+        }
+    }
+
+    #[test]
+    fn too_big_or_empty_items_are_unplaced() {
+        let items = vec![
+            Rectangle::square(11),
+            Rectangle::with_size(0, 5),
+            Rectangle::square(3),
+        ];
+        for &strategy in &STRATEGIES {
+            let packing = Packer::new(strategy).pack_bins(Rectangle::square(10), &items);
+            check(&packing, &items);
+            assert_eq!(packing.unplaced, vec![0, 1]);
+            assert_eq!(packing.bins, 1);
+
+            let none = Packer::new(strategy).pack_bins(Rectangle::square(10), &items[..2]);
+            assert_eq!(none.bins, 0);
+            assert_eq!(none.utilization(), 0.0);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+// Point
+/*
+The generic Point<T> from the generics chapter (`generics::method_def`),
+with the arithmetic it was missing
+
+- Add, Sub, Neg and Mul<T> (scaling) for any T that has them
+- dot product for any T with Add and Mul
+- distance for the float types, through the Float trait
+  (the chapter only had distance_from_origin for Point<f32>)
+- checked_* for the integer types, through the CheckedInt trait (checked.rs),
+  `None` instead of overflowing
+- Point<i32> -> Point<f64> is lossless (From), the way back rounds and can
+  fail (TryFrom)
+ */
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// shared with the Option and Result arithmetic
+pub use crate::checked::CheckedInt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    pub fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, other: Point<T>) -> Point<T> {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, other: Point<T>) -> Point<T> {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+// scaling by a number, p * 2
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point<T> {
+    type Output = Point<T>;
+
+    fn mul(self, factor: T) -> Point<T> {
+        Point::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point<T> {
+    type Output = Point<T>;
+
+    fn neg(self) -> Point<T> {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Add<Output = T> + Mul<Output = T> + Copy> Point<T> {
+    pub fn dot(&self, other: &Point<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
+}
+
+/// Float types a distance can be taken in
+pub trait Float: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    fn zero() -> Self;
+    fn sqrt(self) -> Self;
+}
+
+impl Float for f32 {
+    fn zero() -> f32 {
+        0.0
+    }
+
+    fn sqrt(self) -> f32 {
+        f32::sqrt(self)
+    }
+}
+
+impl Float for f64 {
+    fn zero() -> f64 {
+        0.0
+    }
+
+    fn sqrt(self) -> f64 {
+        f64::sqrt(self)
+    }
+}
+
+impl<T: Float> Point<T> {
+    pub fn length(&self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    pub fn distance(&self, other: &Point<T>) -> T {
+        (*self - *other).length()
+    }
+
+    pub fn distance_from_origin(&self) -> T {
+        self.distance(&Point::new(T::zero(), T::zero()))
+    }
+}
+
+impl<T: CheckedInt> Point<T> {
+    pub fn checked_add(&self, other: &Point<T>) -> Option<Point<T>> {
+        Some(Point::new(
+            self.x.checked_add(other.x)?,
+            self.y.checked_add(other.y)?,
+        ))
+    }
+
+    pub fn checked_sub(&self, other: &Point<T>) -> Option<Point<T>> {
+        Some(Point::new(
+            self.x.checked_sub(other.x)?,
+            self.y.checked_sub(other.y)?,
+        ))
+    }
+
+    pub fn checked_mul(&self, factor: T) -> Option<Point<T>> {
+        Some(Point::new(
+            self.x.checked_mul(factor)?,
+            self.y.checked_mul(factor)?,
+        ))
+    }
+
+    pub fn checked_neg(&self) -> Option<Point<T>> {
+        Some(Point::new(self.x.checked_neg()?, self.y.checked_neg()?))
+    }
+
+    pub fn checked_dot(&self, other: &Point<T>) -> Option<T> {
+        self.x
+            .checked_mul(other.x)?
+            .checked_add(self.y.checked_mul(other.y)?)
+    }
+}
+
+// every i32 is exactly representable as an f64
+impl From<Point<i32>> for Point<f64> {
+    fn from(p: Point<i32>) -> Point<f64> {
+        Point::new(f64::from(p.x), f64::from(p.y))
+    }
+}
+
+/// A Point<f64> with a coordinate that is NaN, infinite or outside the i32
+/// range after rounding
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfRange(pub Point<f64>);
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "point {} doesn't fit in i32 coordinates", self.0)
+    }
+}
+
+impl Error for OutOfRange {}
+
+// rounds to the nearest integer, halfway away from zero
+impl TryFrom<Point<f64>> for Point<i32> {
+    type Error = OutOfRange;
+
+    fn try_from(p: Point<f64>) -> Result<Point<i32>, OutOfRange> {
+        // float -> int casts saturate, so check the range first
+        let to_i32 = |v: f64| {
+            let v = v.round();
+            if v >= f64::from(i32::MIN) && v <= f64::from(i32::MAX) {
+                Some(v as i32)
+            } else {
+                None
+            }
+        };
+        match (to_i32(p.x), to_i32(p.y)) {
+            (Some(x), Some(y)) => Ok(Point::new(x, y)),
+            _ => Err(OutOfRange(p)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Point::new(1, 2);
+        let b = Point::new(3, -4);
+        assert_eq!(a + b, Point::new(4, -2));
+        assert_eq!(a - b, Point::new(-2, 6));
+        assert_eq!(a * 3, Point::new(3, 6));
+        assert_eq!(-a, Point::new(-1, -2));
+        assert_eq!(a.dot(&b), -5);
+        assert_eq!((Point::new(0.5, 1.5) * 2.0), Point::new(1.0, 3.0));
+    }
+
+    #[test]
+    fn distance_for_both_floats() {
+        let p: Point<f32> = Point::new(3.0, 4.0);
+        assert_eq!(p.distance_from_origin(), 5.0);
+
+        let a: Point<f64> = Point::new(1.0, 1.0);
+        let b = Point::new(4.0, 5.0);
+        assert_eq!(a.distance(&b), 5.0);
+        assert_eq!(b.distance(&a), 5.0);
+        assert_eq!(a.distance(&a), 0.0);
+    }
+
+    #[test]
+    fn checked_integer_arithmetic() {
+        let max = Point::new(i32::MAX, 0);
+        let one = Point::new(1, 1);
+        assert_eq!(max.checked_add(&one), None);
+        assert_eq!(max.checked_sub(&one), Some(Point::new(i32::MAX - 1, -1)));
+        assert_eq!(max.checked_mul(2), None);
+        assert_eq!(Point::new(i32::MIN, 0).checked_neg(), None);
+        assert_eq!(max.checked_dot(&one), Some(i32::MAX));
+        assert_eq!(Point::new(i32::MAX, 1).checked_dot(&one), None);
+
+        let small: Point<u8> = Point::new(200, 10);
+        assert_eq!(small.checked_sub(&Point::new(100, 20)), None);
+        assert_eq!(small.checked_neg(), None);
+    }
+
+    #[test]
+    fn converts_between_i32_and_f64() {
+        let p = Point::new(i32::MIN, i32::MAX);
+        let f = Point::<f64>::from(p);
+        assert_eq!(Point::<i32>::try_from(f), Ok(p));
+
+        assert_eq!(
+            Point::<i32>::try_from(Point::new(1.5, -2.4)),
+            Ok(Point::new(2, -2))
+        );
+        // rounds up past i32::MAX
+        let over = Point::new(f64::from(i32::MAX) + 0.5, 0.0);
+        assert_eq!(Point::<i32>::try_from(over), Err(OutOfRange(over)));
+        assert!(Point::<i32>::try_from(Point::new(f64::NAN, 0.0)).is_err());
+        assert!(Point::<i32>::try_from(Point::new(0.0, f64::INFINITY)).is_err());
+    }
+
+    #[test]
+    fn displays_as_a_pair() {
+        assert_eq!(Point::new(1, -2).to_string(), "(1, -2)");
+        assert_eq!(
+            OutOfRange(Point::new(1e10, 0.0)).to_string(),
+            "point (10000000000, 0) doesn't fit in i32 coordinates"
+        );
+    }
+}
@@ -0,0 +1,434 @@
+// QuadTree
+/*
+A spatial index for hit-testing lots of positioned rectangles
+
+- the root covers every position a Rectangle can have, so there is no
+  bounds to pick up front
+- a node splits into four quadrants once it holds more than CAPACITY
+  rectangles; each rectangle lives in the smallest node that contains it
+  whole, so big ones or ones straddling a split stay higher up
+- quadrants whose items were all removed are merged back
+- ItemIds are never reused, a removed id stays invalid
+ */
+use super::Rectangle;
+
+const CAPACITY: usize = 8;
+// a quadrant this deep is a few pixels wide
+const MAX_DEPTH: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemId(pub usize);
+
+/// Squared distance from a point to the closest point of a rectangle,
+/// edges included; 0 inside
+pub fn distance_squared(rect: &Rectangle, x: i64, y: i64) -> u128 {
+    // right and bottom are the closed edges here, a point on them is 0 away
+    squared_gap(rect.left(), rect.right(), x) + squared_gap(rect.top(), rect.bottom(), y)
+}
+
+fn squared_gap(low: i64, high: i64, v: i64) -> u128 {
+    let gap = if v < low {
+        low - v
+    } else if v > high {
+        v - high
+    } else {
+        0
+    };
+    let gap = gap as u128;
+    gap * gap
+}
+
+// node bounds in i64, half-open like Rectangle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+}
+
+impl Bounds {
+    fn contains(&self, r: &Rectangle) -> bool {
+        self.left <= r.left()
+            && r.right() <= self.right
+            && self.top <= r.top()
+            && r.bottom() <= self.bottom
+    }
+
+    fn overlaps(&self, r: &Rectangle) -> bool {
+        self.left < r.right()
+            && r.left() < self.right
+            && self.top < r.bottom()
+            && r.top() < self.bottom
+    }
+
+    fn contains_point(&self, x: i64, y: i64) -> bool {
+        self.left <= x && x < self.right && self.top <= y && y < self.bottom
+    }
+
+    fn distance_squared(&self, x: i64, y: i64) -> u128 {
+        squared_gap(self.left, self.right, x) + squared_gap(self.top, self.bottom, y)
+    }
+
+    fn quadrants(&self) -> [Bounds; 4] {
+        let mid_x = self.left + (self.right - self.left) / 2;
+        let mid_y = self.top + (self.bottom - self.top) / 2;
+        [
+            Bounds {
+                right: mid_x,
+                bottom: mid_y,
+                ..*self
+            },
+            Bounds {
+                left: mid_x,
+                bottom: mid_y,
+                ..*self
+            },
+            Bounds {
+                right: mid_x,
+                top: mid_y,
+                ..*self
+            },
+            Bounds {
+                left: mid_x,
+                top: mid_y,
+                ..*self
+            },
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Bounds,
+    depth: u32,
+    items: Vec<ItemId>,
+    children: Option<Box<[Node; 4]>>,
+}
+
+impl Node {
+    fn new(bounds: Bounds, depth: u32) -> Node {
+        Node {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    // the child that contains `rect` whole, if any
+    fn child_for(&mut self, rect: &Rectangle) -> Option<&mut Node> {
+        self.children
+            .as_mut()?
+            .iter_mut()
+            .find(|c| c.bounds.contains(rect))
+    }
+
+    fn insert(&mut self, id: ItemId, rect: &Rectangle, rects: &[Option<Rectangle>]) {
+        if let Some(child) = self.child_for(rect) {
+            return child.insert(id, rect, rects);
+        }
+        self.items.push(id);
+        if self.children.is_none() && self.items.len() > CAPACITY && self.depth < MAX_DEPTH {
+            self.split(rects);
+        }
+    }
+
+    fn split(&mut self, rects: &[Option<Rectangle>]) {
+        let [a, b, c, d] = self.bounds.quadrants();
+        let depth = self.depth + 1;
+        self.children = Some(Box::new([
+            Node::new(a, depth),
+            Node::new(b, depth),
+            Node::new(c, depth),
+            Node::new(d, depth),
+        ]));
+        for id in std::mem::take(&mut self.items) {
+            let rect = rects[id.0].expect("indexed items are present");
+            self.insert(id, &rect, rects);
+        }
+    }
+
+    fn remove(&mut self, id: ItemId, rect: &Rectangle) -> bool {
+        let removed = match self.child_for(rect) {
+            Some(child) => child.remove(id, rect),
+            None => match self.items.iter().position(|&i| i == id) {
+                Some(idx) => {
+                    self.items.swap_remove(idx);
+                    true
+                }
+                None => false,
+            },
+        };
+        if removed {
+            self.merge();
+        }
+        removed
+    }
+
+    // pulls the children back in once they are leaves that fit here
+    fn merge(&mut self) {
+        let children = match &mut self.children {
+            Some(children) => children,
+            None => return,
+        };
+        if children.iter().any(|c| c.children.is_some()) {
+            return;
+        }
+        let total: usize = children.iter().map(|c| c.items.len()).sum();
+        if self.items.len() + total > CAPACITY {
+            return;
+        }
+        for child in children.iter_mut() {
+            self.items.append(&mut child.items);
+        }
+        self.children = None;
+    }
+
+    fn query_point(&self, x: i64, y: i64, rects: &[Option<Rectangle>], out: &mut Vec<ItemId>) {
+        out.extend(
+            self.items
+                .iter()
+                .filter(|id| rects[id.0].is_some_and(|r| r.contains_point(x, y))),
+        );
+        if let Some(children) = &self.children {
+            for child in children.iter() {
+                if child.bounds.contains_point(x, y) {
+                    child.query_point(x, y, rects, out);
+                }
+            }
+        }
+    }
+
+    fn query_range(&self, area: &Rectangle, rects: &[Option<Rectangle>], out: &mut Vec<ItemId>) {
+        out.extend(
+            self.items
+                .iter()
+                .filter(|id| rects[id.0].is_some_and(|r| r.overlaps(area))),
+        );
+        if let Some(children) = &self.children {
+            for child in children.iter() {
+                if child.bounds.overlaps(area) {
+                    child.query_range(area, rects, out);
+                }
+            }
+        }
+    }
+
+    // branch and bound, `best` is (distance, id) so ties go to the lowest id
+    fn nearest(
+        &self,
+        x: i64,
+        y: i64,
+        rects: &[Option<Rectangle>],
+        best: &mut Option<(u128, ItemId)>,
+    ) {
+        for &id in &self.items {
+            if let Some(rect) = &rects[id.0] {
+                let candidate = (distance_squared(rect, x, y), id);
+                if best.is_none_or(|b| candidate < b) {
+                    *best = Some(candidate);
+                }
+            }
+        }
+        if let Some(children) = &self.children {
+            let mut order: Vec<(u128, &Node)> = children
+                .iter()
+                .map(|c| (c.bounds.distance_squared(x, y), c))
+                .collect();
+            order.sort_by_key(|&(distance, _)| distance);
+            for (distance, child) in order {
+                // equal distance can still win on id
+                if best.is_some_and(|(d, _)| distance > d) {
+                    break;
+                }
+                child.nearest(x, y, rects, best);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuadTree {
+    root: Node,
+    // by ItemId, None once removed
+    rects: Vec<Option<Rectangle>>,
+    len: usize,
+}
+
+impl Default for QuadTree {
+    fn default() -> QuadTree {
+        QuadTree::new()
+    }
+}
+
+impl QuadTree {
+    pub fn new() -> QuadTree {
+        // wide enough for a u32::MAX wide rectangle at i32::MAX, and a
+        // power of two so quadrants split evenly
+        let root = Bounds {
+            left: -(1 << 33),
+            top: -(1 << 33),
+            right: 1 << 33,
+            bottom: 1 << 33,
+        };
+        QuadTree {
+            root: Node::new(root, 0),
+            rects: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, rect: Rectangle) -> ItemId {
+        let id = ItemId(self.rects.len());
+        self.rects.push(Some(rect));
+        self.root.insert(id, &rect, &self.rects);
+        self.len += 1;
+        id
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&Rectangle> {
+        self.rects.get(id.0)?.as_ref()
+    }
+
+    pub fn remove(&mut self, id: ItemId) -> Option<Rectangle> {
+        let rect = self.rects.get_mut(id.0)?.take()?;
+        let removed = self.root.remove(id, &rect);
+        debug_assert!(removed, "{:?} was not in the tree", id);
+        self.len -= 1;
+        Some(rect)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, &Rectangle)> {
+        self.rects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.as_ref().map(|r| (ItemId(i), r)))
+    }
+
+    /// Rectangles containing the point, by id
+    pub fn query_point(&self, x: i64, y: i64) -> Vec<ItemId> {
+        let mut out = Vec::new();
+        self.root.query_point(x, y, &self.rects, &mut out);
+        out.sort_unstable();
+        out
+    }
+
+    /// Rectangles overlapping `area`, by id; touching edges don't count
+    pub fn query_range(&self, area: &Rectangle) -> Vec<ItemId> {
+        let mut out = Vec::new();
+        self.root.query_range(area, &self.rects, &mut out);
+        out.sort_unstable();
+        out
+    }
+
+    /// The rectangle closest to the point and its squared distance (see
+    /// `distance_squared`), the lowest id on ties
+    pub fn nearest(&self, x: i64, y: i64) -> Option<(ItemId, u128)> {
+        let mut best = None;
+        self.root.nearest(x, y, &self.rects, &mut best);
+        best.map(|(distance, id)| (id, distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn brute_point(rects: &[Option<Rectangle>], x: i64, y: i64) -> Vec<ItemId> {
+        (0..rects.len())
+            .filter(|&i| rects[i].is_some_and(|r| r.contains_point(x, y)))
+            .map(ItemId)
+            .collect()
+    }
+
+    fn brute_range(rects: &[Option<Rectangle>], area: &Rectangle) -> Vec<ItemId> {
+        (0..rects.len())
+            .filter(|&i| rects[i].is_some_and(|r| r.overlaps(area)))
+            .map(ItemId)
+            .collect()
+    }
+
+    fn brute_nearest(rects: &[Option<Rectangle>], x: i64, y: i64) -> Option<(ItemId, u128)> {
+        (0..rects.len())
+            .filter_map(|i| rects[i].map(|r| (distance_squared(&r, x, y), ItemId(i))))
+            .min()
+            .map(|(d, id)| (id, d))
+    }
+
+    fn rect(span: i32, size: u32) -> impl Strategy<Value = Rectangle> {
+        (-span..span, -span..span, 0..size, 0..size)
+            .prop_map(|(x, y, w, h)| Rectangle::new(x, y, w, h))
+    }
+
+    #[test]
+    fn splits_and_merges_back() {
+        let mut tree = QuadTree::new();
+        let ids: Vec<ItemId> = (0..100)
+            .map(|i| tree.insert(Rectangle::new(i * 10, i * 10, 5, 5)))
+            .collect();
+        assert!(tree.root.children.is_some());
+        assert_eq!(tree.query_point(502, 502), vec![ids[50]]);
+
+        for &id in &ids[1..] {
+            assert!(tree.remove(id).is_some());
+        }
+        assert!(tree.remove(ids[1]).is_none());
+        assert_eq!(tree.len(), 1);
+        assert!(tree.root.children.is_none());
+        assert_eq!(tree.nearest(1000, 1000), Some((ids[0], 2 * 995 * 995)));
+    }
+
+    #[test]
+    fn handles_the_extremes() {
+        let mut tree = QuadTree::new();
+        let far = tree.insert(Rectangle::new(i32::MAX, i32::MAX, u32::MAX, u32::MAX));
+        let near = tree.insert(Rectangle::new(i32::MIN, i32::MIN, 1, 1));
+        let x = i64::from(i32::MAX) + i64::from(u32::MAX) - 1;
+        assert_eq!(tree.query_point(x, x), vec![far]);
+        assert_eq!(tree.nearest(i64::from(i32::MIN), 0).unwrap().0, near);
+        assert_eq!(tree.nearest(-1, -1).unwrap().0, near);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_a_brute_force_scan(
+            rects in prop::collection::vec(rect(1000, 300), 0..300),
+            removals in prop::collection::vec(any::<prop::sample::Index>(), 0..100),
+            points in prop::collection::vec((-1200i64..1200, -1200i64..1200), 1..20),
+            areas in prop::collection::vec(rect(1200, 600), 1..20),
+        ) {
+            let mut tree = QuadTree::new();
+            let mut all: Vec<Option<Rectangle>> = Vec::new();
+            for r in &rects {
+                let id = tree.insert(*r);
+                prop_assert_eq!(id, ItemId(all.len()));
+                all.push(Some(*r));
+            }
+            if !rects.is_empty() {
+                for idx in &removals {
+                    let i = idx.index(rects.len());
+                    prop_assert_eq!(tree.remove(ItemId(i)), all[i].take());
+                }
+            }
+            prop_assert_eq!(tree.len(), all.iter().flatten().count());
+
+            for &(x, y) in &points {
+                prop_assert_eq!(tree.query_point(x, y), brute_point(&all, x, y));
+                prop_assert_eq!(tree.nearest(x, y), brute_nearest(&all, x, y));
+            }
+            for area in &areas {
+                prop_assert_eq!(tree.query_range(area), brute_range(&all, area));
+            }
+        }
+    }
+}
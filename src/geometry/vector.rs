@@ -0,0 +1,175 @@
+// Vec3
+/*
+A 3D vector, grown out of `struct Point(i32, i32, i32)` in
+`structs::tuple_struct_simple`
+
+- f64 components, used for points and directions alike
+- right-handed: X cross Y is Z
+- see geometry::matrix for rotating, scaling and translating them
+ */
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::approx::ApproxEq;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+    pub const X: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+    pub const Y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+    pub const Z: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+
+    pub fn dot(&self, other: &Vec3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(&self, other: &Vec3) -> f64 {
+        (*self - *other).length()
+    }
+
+    /// Same direction, length 1; `None` for the zero vector (or one too
+    /// small or big to divide by its length)
+    pub fn normalized(&self) -> Option<Vec3> {
+        let length = self.length();
+        if length == 0.0 || !length.is_finite() {
+            return None;
+        }
+        Some(*self / length)
+    }
+
+    pub fn to_array(&self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+// the tuple struct from the chapter: Point(i32, i32, i32)
+impl From<(i32, i32, i32)> for Vec3 {
+    fn from((x, y, z): (i32, i32, i32)) -> Vec3 {
+        Vec3::new(f64::from(x), f64::from(y), f64::from(z))
+    }
+}
+
+impl From<[f64; 3]> for Vec3 {
+    fn from([x, y, z]: [f64; 3]) -> Vec3 {
+        Vec3::new(x, y, z)
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, factor: f64) -> Vec3 {
+        Vec3::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Div<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn div(self, divisor: f64) -> Vec3 {
+        Vec3::new(self.x / divisor, self.y / divisor, self.z / divisor)
+    }
+}
+
+impl ApproxEq for Vec3 {
+    fn approx_eq(&self, other: &Vec3, epsilon: f64) -> bool {
+        self.to_array().approx_eq(&other.to_array(), epsilon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_approx_eq;
+
+    #[test]
+    fn products() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, -5.0, 6.0);
+        assert_eq!(a.dot(&b), 12.0);
+        assert_eq!(a.cross(&b), Vec3::new(27.0, 6.0, -13.0));
+        // perpendicular to both
+        assert_eq!(a.cross(&b).dot(&a), 0.0);
+        assert_eq!(a.cross(&b).dot(&b), 0.0);
+        assert_eq!(Vec3::X.cross(&Vec3::Y), Vec3::Z);
+        assert_eq!(a.cross(&a), Vec3::ZERO);
+    }
+
+    #[test]
+    fn normalizes() {
+        let v = Vec3::new(3.0, 0.0, 4.0);
+        assert_eq!(v.length(), 5.0);
+        assert_approx_eq!(v.normalized().unwrap(), Vec3::new(0.6, 0.0, 0.8));
+        assert_approx_eq!(v.normalized().unwrap().length(), 1.0);
+        assert_eq!(Vec3::ZERO.normalized(), None);
+        assert_eq!(Vec3::new(f64::INFINITY, 0.0, 0.0).normalized(), None);
+    }
+
+    #[test]
+    fn arithmetic_and_conversions() {
+        let origin = Vec3::from((0, 0, 0));
+        let p = Vec3::from((1, 2, 2));
+        assert_eq!(p.distance(&origin), 3.0);
+        assert_eq!(p + p, p * 2.0);
+        assert_eq!(p - p, Vec3::ZERO);
+        assert_eq!(-p / 2.0, Vec3::new(-0.5, -1.0, -1.0));
+        assert_eq!(Vec3::from(p.to_array()), p);
+        assert_eq!(p.to_string(), "(1, 2, 2)");
+    }
+}
@@ -0,0 +1,20 @@
+// Library
+/*
+Reusable pieces grown out of the chapter examples in src/main.rs
+
+src/main.rs is the binary crate root, src/lib.rs the library crate root
+(see packages_crates.rs), so these can be used from other crates and
+get integration tests
+ */
+pub mod app_error;
+pub mod checked;
+pub mod coins;
+pub mod color;
+pub mod geometry;
+pub mod messages;
+pub mod net;
+pub mod rules;
+pub mod users;
+
+#[cfg(test)]
+mod test_util;
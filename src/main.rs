@@ -52,11 +52,8 @@ fn main() {
     // collections::exercises::basic_stats::output();
 
     // errors::panic_example();
-    // errors::recoverable::open_file();
-    // errors::recoverable::handling_closures();
-    // errors::recoverable::unwrap_example();
-    // errors::recoverable::expect_example();
-    // errors::recoverable::propagating();
+    // the recoverable examples are in the library, with tests:
+    // rust_example::app_error::recoverable
     // errors::panic_or_not;
 
     // generics::remove_duplication::extract_function_example();
@@ -0,0 +1,55 @@
+// Messages
+/*
+The Message enum from `enums::compared_to_struct`, as a real type that can
+be sent somewhere
+
+- each variant carries different data, which is why it's an enum and not
+  four structs: anything that handles messages takes one type
+ */
+// bounded channel between threads, in src/messages/channel.rs
+pub mod channel;
+// handlers, middleware and replay, in src/messages/dispatch.rs
+pub mod dispatch;
+// binary encoding, in src/messages/wire.rs
+pub mod wire;
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Message {
+    Quit,
+    Move { x: i32, y: i32 },
+    Write(String),
+    ChangeColor(i32, i32, i32),
+}
+
+// which variant, without its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MessageKind {
+    Quit,
+    Move,
+    Write,
+    ChangeColor,
+}
+
+impl Message {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Message::Quit => MessageKind::Quit,
+            Message::Move { .. } => MessageKind::Move,
+            Message::Write(_) => MessageKind::Write,
+            Message::ChangeColor(..) => MessageKind::ChangeColor,
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Quit => write!(f, "quit"),
+            Message::Move { x, y } => write!(f, "move to ({}, {})", x, y),
+            Message::Write(text) => write!(f, "write {:?}", text),
+            Message::ChangeColor(r, g, b) => write!(f, "change color to ({}, {}, {})", r, g, b),
+        }
+    }
+}
@@ -0,0 +1,420 @@
+// Channel
+/*
+A bounded queue of Messages between threads, like std::sync::mpsc's
+sync_channel but with a choice of what happens when it's full
+
+- Block waits for the receiver to make room, DropOldest throws away the
+  message at the front, Error hands the message back as SendError::Full
+- sending Quit closes the channel: it is always accepted, even when full,
+  later sends fail with SendError::Closed, and the receiver still gets
+  everything before it, then the Quit, then None
+- dropping the Receiver closes it too; dropping every Sender ends it for
+  the receiver once it's empty
+- one Mutex around the queue and two Condvars, not_empty for the receiver
+  and not_full for blocked senders
+- latency is the time between send and recv, throughput counts received
+  messages per second since the channel was made
+ */
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::Message;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    Block,
+    DropOldest,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    Full(Message),
+    Closed(Message),
+}
+
+impl SendError {
+    /// The message that wasn't sent
+    pub fn into_message(self) -> Message {
+        match self {
+            SendError::Full(m) | SendError::Closed(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SendError::Full(m) => write!(f, "channel full, could not {}", m),
+            SendError::Closed(m) => write!(f, "channel closed, could not {}", m),
+        }
+    }
+}
+
+impl Error for SendError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Closed,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel empty"),
+            TryRecvError::Closed => write!(f, "channel closed"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+/// Counters since the channel was made
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub sent: u64,
+    pub received: u64,
+    // thrown away by DropOldest
+    pub dropped: u64,
+    // handed back as Full
+    pub rejected: u64,
+    // sends that had to wait for room
+    pub blocked: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    pub fn mean_latency(&self) -> Option<Duration> {
+        if self.received == 0 {
+            return None;
+        }
+        Some(self.total_latency.div_f64(self.received as f64))
+    }
+
+    /// Received messages per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.received as f64 / secs
+        }
+    }
+}
+
+struct Queue {
+    messages: VecDeque<(Message, Instant)>,
+    // Quit was sent or the receiver is gone
+    closed: bool,
+    senders: usize,
+    stats: Stats,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    backpressure: Backpressure,
+    started: Instant,
+}
+
+impl Shared {
+    // a thread panicking with the lock can't leave the queue half updated,
+    // so carry on with it
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn stats(&self) -> Stats {
+        let mut stats = self.lock().stats;
+        stats.elapsed = self.started.elapsed();
+        stats
+    }
+}
+
+/// A channel holding at most `capacity` messages (at least 1), plus a Quit
+pub fn channel(capacity: usize, backpressure: Backpressure) -> (Sender, Receiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Queue {
+            messages: VecDeque::new(),
+            closed: false,
+            senders: 1,
+            stats: Stats {
+                sent: 0,
+                received: 0,
+                dropped: 0,
+                rejected: 0,
+                blocked: 0,
+                total_latency: Duration::from_secs(0),
+                max_latency: Duration::from_secs(0),
+                elapsed: Duration::from_secs(0),
+            },
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        capacity: capacity.max(1),
+        backpressure,
+        started: Instant::now(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+pub struct Sender {
+    shared: Arc<Shared>,
+}
+
+impl Sender {
+    pub fn send(&self, message: Message) -> Result<(), SendError> {
+        let shared = &*self.shared;
+        let mut queue = shared.lock();
+        if queue.closed {
+            return Err(SendError::Closed(message));
+        }
+        let quit = message == Message::Quit;
+        if !quit && queue.messages.len() >= shared.capacity {
+            match shared.backpressure {
+                Backpressure::Block => {
+                    queue.stats.blocked += 1;
+                    while !queue.closed && queue.messages.len() >= shared.capacity {
+                        queue = shared
+                            .not_full
+                            .wait(queue)
+                            .unwrap_or_else(|e| e.into_inner());
+                    }
+                    if queue.closed {
+                        return Err(SendError::Closed(message));
+                    }
+                }
+                Backpressure::DropOldest => {
+                    queue.messages.pop_front();
+                    queue.stats.dropped += 1;
+                }
+                Backpressure::Error => {
+                    queue.stats.rejected += 1;
+                    return Err(SendError::Full(message));
+                }
+            }
+        }
+        queue.messages.push_back((message, Instant::now()));
+        queue.stats.sent += 1;
+        if quit {
+            queue.closed = true;
+            // blocked senders give up
+            shared.not_full.notify_all();
+        }
+        shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.shared.stats()
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Sender {
+        self.shared.lock().senders += 1;
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock();
+        queue.senders -= 1;
+        if queue.senders == 0 {
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+pub struct Receiver {
+    shared: Arc<Shared>,
+}
+
+impl Receiver {
+    fn take(&self, queue: &mut Queue) -> Option<Message> {
+        let (message, sent_at) = queue.messages.pop_front()?;
+        let latency = sent_at.elapsed();
+        let stats = &mut queue.stats;
+        stats.received += 1;
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+        self.shared.not_full.notify_one();
+        Some(message)
+    }
+
+    /// The next message, waiting for one; `None` once the channel is
+    /// closed and empty or every Sender is gone
+    pub fn recv(&self) -> Option<Message> {
+        let shared = &*self.shared;
+        let mut queue = shared.lock();
+        loop {
+            if let Some(message) = self.take(&mut queue) {
+                return Some(message);
+            }
+            if queue.closed || queue.senders == 0 {
+                return None;
+            }
+            queue = shared
+                .not_empty
+                .wait(queue)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<Message, TryRecvError> {
+        let mut queue = self.shared.lock();
+        match self.take(&mut queue) {
+            Some(message) => Ok(message),
+            None if queue.closed || queue.senders == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.shared.stats()
+    }
+}
+
+impl Iterator for Receiver {
+    type Item = Message;
+
+    fn next(&mut self) -> Option<Message> {
+        self.recv()
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.not_full.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn numbered(n: i32) -> Message {
+        Message::Move { x: 0, y: n }
+    }
+
+    #[test]
+    fn many_producers_one_consumer() {
+        const PRODUCERS: i32 = 8;
+        const EACH: i32 = 500;
+        let (tx, mut rx) = channel(4, Backpressure::Block);
+
+        let consumer = thread::spawn(move || {
+            let received: Vec<Message> = rx.by_ref().collect();
+            (received, rx.stats())
+        });
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..EACH {
+                        tx.send(Message::Move { x: p, y: i }).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        tx.send(Message::Quit).unwrap();
+        assert_eq!(tx.send(numbered(0)), Err(SendError::Closed(numbered(0))));
+
+        let (received, stats) = consumer.join().unwrap();
+        assert_eq!(received.len() as i32, PRODUCERS * EACH + 1);
+        assert_eq!(received.last(), Some(&Message::Quit));
+        // every producer's messages arrive in the order it sent them
+        let mut next = vec![0; PRODUCERS as usize];
+        for m in &received[..received.len() - 1] {
+            match *m {
+                Message::Move { x, y } => {
+                    assert_eq!(next[x as usize], y);
+                    next[x as usize] += 1;
+                }
+                ref other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert_eq!(stats.sent, stats.received);
+        assert_eq!(stats.dropped + stats.rejected, 0);
+        assert!(stats.max_latency >= stats.mean_latency().unwrap());
+        assert!(stats.throughput() > 0.0);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_newest() {
+        let (tx, rx) = channel(2, Backpressure::DropOldest);
+        for i in 0..5 {
+            tx.send(numbered(i)).unwrap();
+        }
+        assert_eq!(rx.try_recv(), Ok(numbered(3)));
+        assert_eq!(rx.try_recv(), Ok(numbered(4)));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(rx.stats().dropped, 3);
+    }
+
+    #[test]
+    fn error_hands_the_message_back() {
+        let (tx, rx) = channel(1, Backpressure::Error);
+        tx.send(numbered(1)).unwrap();
+        let err = tx.send(numbered(2)).unwrap_err();
+        assert_eq!(err, SendError::Full(numbered(2)));
+        assert_eq!(err.into_message(), numbered(2));
+        // Quit gets in even when full
+        tx.send(Message::Quit).unwrap();
+        assert_eq!(rx.collect::<Vec<_>>(), vec![numbered(1), Message::Quit]);
+    }
+
+    #[test]
+    fn quit_wakes_blocked_senders() {
+        let (tx, rx) = channel(1, Backpressure::Block);
+        tx.send(numbered(1)).unwrap();
+        let blocked = {
+            let tx = tx.clone();
+            thread::spawn(move || tx.send(numbered(2)))
+        };
+        // wait until it's waiting for room
+        while tx.stats().blocked == 0 {
+            thread::yield_now();
+        }
+        tx.send(Message::Quit).unwrap();
+        assert_eq!(blocked.join().unwrap(), Err(SendError::Closed(numbered(2))));
+        assert_eq!(rx.recv(), Some(numbered(1)));
+        assert_eq!(rx.recv(), Some(Message::Quit));
+        assert_eq!(rx.recv(), None);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+    }
+
+    #[test]
+    fn either_end_going_away_closes_it() {
+        let (tx, rx) = channel(1, Backpressure::Block);
+        let waiting = thread::spawn(move || rx.recv());
+        drop(tx);
+        assert_eq!(waiting.join().unwrap(), None);
+
+        let (tx, rx) = channel(1, Backpressure::Block);
+        tx.send(numbered(1)).unwrap();
+        drop(rx);
+        assert_eq!(tx.send(numbered(2)), Err(SendError::Closed(numbered(2))));
+    }
+}
@@ -0,0 +1,535 @@
+// Dispatch
+/*
+What `Message::call` in `enums::compared_to_struct` would do: run a message
+against a State
+
+- handlers are registered per MessageKind, several may share one and run
+  in the order they were added; `Dispatcher::standard()` starts with the
+  ones that move the cursor, append text, change the color and stop
+- the handlers for a message work on a copy of the State, which only
+  replaces it if they all succeed: a failed message changes nothing, so
+  leaving it out of a recording loses nothing
+- middleware wraps every dispatch: `before` in the order added can skip
+  the message, `after` in reverse order sees how it went; every `after`
+  runs even if one fails, the first error is returned at the end
+- once a Quit has been handled the State is stopped and any further
+  message is an error
+- a Recorder writes the handled messages as wire frames, `replay` runs
+  them again to rebuild the State; replay through a Dispatcher without
+  the Recorder, or the messages get recorded twice
+ */
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use super::wire::{self, Decoder, WireError};
+use super::{Message, MessageKind};
+use crate::color::{Color, ColorError};
+use crate::geometry::point::Point;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub cursor: Point<i32>,
+    pub text: String,
+    pub color: Color,
+    pub running: bool,
+}
+
+impl Default for State {
+    fn default() -> State {
+        State {
+            cursor: Point { x: 0, y: 0 },
+            text: String::new(),
+            color: Color::BLACK,
+            running: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DispatchError {
+    NoHandler(MessageKind),
+    // a message after Quit
+    Stopped,
+    Color(ColorError),
+    // from a handler that isn't one of the standard ones
+    Failed(String),
+    Wire(WireError),
+    Io(io::Error),
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispatchError::NoHandler(kind) => write!(f, "no handler for {:?}", kind),
+            DispatchError::Stopped => write!(f, "already quit"),
+            DispatchError::Color(e) => write!(f, "{}", e),
+            DispatchError::Failed(reason) => write!(f, "{}", reason),
+            DispatchError::Wire(e) => write!(f, "bad recording: {}", e),
+            DispatchError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for DispatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DispatchError::Color(e) => Some(e),
+            DispatchError::Wire(e) => Some(e),
+            DispatchError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ColorError> for DispatchError {
+    fn from(e: ColorError) -> DispatchError {
+        DispatchError::Color(e)
+    }
+}
+
+impl From<WireError> for DispatchError {
+    fn from(e: WireError) -> DispatchError {
+        DispatchError::Wire(e)
+    }
+}
+
+impl From<io::Error> for DispatchError {
+    fn from(e: io::Error) -> DispatchError {
+        DispatchError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Handled,
+    // a middleware's before said so
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Skip,
+}
+
+pub trait Middleware {
+    fn before(&mut self, _message: &Message, _state: &State) -> Flow {
+        Flow::Continue
+    }
+
+    fn after(
+        &mut self,
+        _message: &Message,
+        _state: &State,
+        _outcome: &Result<Outcome, DispatchError>,
+    ) -> Result<(), DispatchError> {
+        Ok(())
+    }
+}
+
+/// Writes a line per message: what it was and how it went
+pub struct Logger<W> {
+    out: W,
+}
+
+impl<W: io::Write> Logger<W> {
+    pub fn new(out: W) -> Logger<W> {
+        Logger { out }
+    }
+}
+
+impl<W: io::Write> Middleware for Logger<W> {
+    fn after(
+        &mut self,
+        message: &Message,
+        _state: &State,
+        outcome: &Result<Outcome, DispatchError>,
+    ) -> Result<(), DispatchError> {
+        match outcome {
+            Ok(Outcome::Handled) => writeln!(self.out, "{}: ok", message)?,
+            Ok(Outcome::Skipped) => writeln!(self.out, "{}: skipped", message)?,
+            Err(e) => writeln!(self.out, "{}: {}", message, e)?,
+        }
+        Ok(())
+    }
+}
+
+/// Skips the messages the predicate says no to
+pub struct Filter<F> {
+    keep: F,
+}
+
+impl<F: FnMut(&Message) -> bool> Filter<F> {
+    pub fn new(keep: F) -> Filter<F> {
+        Filter { keep }
+    }
+}
+
+impl<F: FnMut(&Message) -> bool> Middleware for Filter<F> {
+    fn before(&mut self, message: &Message, _state: &State) -> Flow {
+        if (self.keep)(message) {
+            Flow::Continue
+        } else {
+            Flow::Skip
+        }
+    }
+}
+
+/// Writes every handled message as a wire frame, for `replay`
+pub struct Recorder<W> {
+    out: W,
+}
+
+impl<W: io::Write> Recorder<W> {
+    pub fn new(out: W) -> Recorder<W> {
+        Recorder { out }
+    }
+}
+
+impl<W: io::Write> Middleware for Recorder<W> {
+    fn after(
+        &mut self,
+        message: &Message,
+        _state: &State,
+        outcome: &Result<Outcome, DispatchError>,
+    ) -> Result<(), DispatchError> {
+        if let Ok(Outcome::Handled) = outcome {
+            self.out.write_all(&wire::encode(message))?;
+        }
+        Ok(())
+    }
+}
+
+type Handler<'a> = Box<dyn FnMut(&mut State, &Message) -> Result<(), DispatchError> + 'a>;
+
+/// Handlers and middleware, see the notes at the top
+///
+/// The lifetime lets middleware write to something borrowed, like a
+/// `Logger::new(&mut buf)`.
+#[derive(Default)]
+pub struct Dispatcher<'a> {
+    handlers: HashMap<MessageKind, Vec<Handler<'a>>>,
+    middleware: Vec<Box<dyn Middleware + 'a>>,
+}
+
+impl<'a> Dispatcher<'a> {
+    /// No handlers at all
+    pub fn new() -> Dispatcher<'a> {
+        Dispatcher::default()
+    }
+
+    /// A handler for every kind, updating the State the obvious way
+    pub fn standard() -> Dispatcher<'a> {
+        let mut d = Dispatcher::new();
+        d.on(MessageKind::Quit, |state, _| {
+            state.running = false;
+            Ok(())
+        });
+        d.on(MessageKind::Move, |state, message| {
+            if let Message::Move { x, y } = *message {
+                state.cursor = Point { x, y };
+            }
+            Ok(())
+        });
+        d.on(MessageKind::Write, |state, message| {
+            if let Message::Write(text) = message {
+                state.text.push_str(text);
+            }
+            Ok(())
+        });
+        d.on(MessageKind::ChangeColor, |state, message| {
+            if let Message::ChangeColor(r, g, b) = *message {
+                state.color = Color::try_from((r, g, b))?;
+            }
+            Ok(())
+        });
+        d
+    }
+
+    /// Adds a handler for one kind, after any already there
+    pub fn on<F>(&mut self, kind: MessageKind, handler: F) -> &mut Dispatcher<'a>
+    where
+        F: FnMut(&mut State, &Message) -> Result<(), DispatchError> + 'a,
+    {
+        self.handlers
+            .entry(kind)
+            .or_default()
+            .push(Box::new(handler));
+        self
+    }
+
+    pub fn layer<M: Middleware + 'a>(&mut self, middleware: M) -> &mut Dispatcher<'a> {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    pub fn dispatch(
+        &mut self,
+        state: &mut State,
+        message: &Message,
+    ) -> Result<Outcome, DispatchError> {
+        let mut ran = 0;
+        let mut outcome = Ok(Outcome::Skipped);
+        let skipped = self.middleware.iter_mut().any(|m| {
+            ran += 1;
+            m.before(message, state) == Flow::Skip
+        });
+        if !skipped {
+            outcome = self.handle(state, message);
+        }
+        let mut failed = None;
+        for m in self.middleware[..ran].iter_mut().rev() {
+            if let Err(e) = m.after(message, state, &outcome) {
+                failed.get_or_insert(e);
+            }
+        }
+        match failed {
+            Some(e) => Err(e),
+            None => outcome,
+        }
+    }
+
+    fn handle(&mut self, state: &mut State, message: &Message) -> Result<Outcome, DispatchError> {
+        if !state.running {
+            return Err(DispatchError::Stopped);
+        }
+        let kind = message.kind();
+        let handlers = match self.handlers.get_mut(&kind) {
+            Some(handlers) if !handlers.is_empty() => handlers,
+            _ => return Err(DispatchError::NoHandler(kind)),
+        };
+        let mut next = state.clone();
+        for handler in handlers {
+            handler(&mut next, message)?;
+        }
+        *state = next;
+        Ok(Outcome::Handled)
+    }
+
+    /// Dispatches every frame a Recorder wrote, stopping at the first
+    /// error, and returns how many were handled
+    pub fn replay(&mut self, state: &mut State, recording: &[u8]) -> Result<usize, DispatchError> {
+        let mut decoder = Decoder::new();
+        decoder.feed(recording);
+        let mut handled = 0;
+        while let Some(message) = decoder.next_message()? {
+            if self.dispatch(state, &message)? == Outcome::Handled {
+                handled += 1;
+            }
+        }
+        if decoder.buffered() > 0 {
+            return Err(DispatchError::Wire(WireError::Truncated));
+        }
+        Ok(handled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Vec<Message> {
+        vec![
+            Message::Write(String::from("hello")),
+            Message::Move { x: 3, y: -4 },
+            Message::ChangeColor(255, 128, 0),
+            Message::Write(String::from(", world")),
+            Message::Quit,
+        ]
+    }
+
+    #[test]
+    fn standard_handlers_update_the_state() {
+        let mut d = Dispatcher::standard();
+        let mut state = State::default();
+        for m in &session() {
+            assert_eq!(d.dispatch(&mut state, m).unwrap(), Outcome::Handled);
+        }
+        assert_eq!(
+            state,
+            State {
+                cursor: Point { x: 3, y: -4 },
+                text: String::from("hello, world"),
+                color: Color::rgb(255, 128, 0),
+                running: false,
+            }
+        );
+        assert!(matches!(
+            d.dispatch(&mut state, &Message::Move { x: 0, y: 0 }),
+            Err(DispatchError::Stopped)
+        ));
+    }
+
+    #[test]
+    fn handler_errors() {
+        let mut d = Dispatcher::standard();
+        let mut state = State::default();
+        assert!(matches!(
+            d.dispatch(&mut state, &Message::ChangeColor(0, 300, 0)),
+            Err(DispatchError::Color(ColorError::ChannelOutOfRange(300)))
+        ));
+        assert_eq!(state.color, Color::BLACK);
+
+        let mut empty = Dispatcher::new();
+        assert!(matches!(
+            empty.dispatch(&mut state, &Message::Quit),
+            Err(DispatchError::NoHandler(MessageKind::Quit))
+        ));
+    }
+
+    #[test]
+    fn extra_handlers_run_in_order() {
+        let mut moves = Vec::new();
+        {
+            let mut d = Dispatcher::standard();
+            d.on(MessageKind::Move, |state, _| {
+                moves.push(state.cursor);
+                Ok(())
+            });
+            d.on(MessageKind::Write, |_, m| match m {
+                Message::Write(text) if text.is_empty() => {
+                    Err(DispatchError::Failed(String::from("empty")))
+                }
+                _ => Ok(()),
+            });
+            let mut state = State::default();
+            d.dispatch(&mut state, &Message::Move { x: 1, y: 1 })
+                .unwrap();
+            d.dispatch(&mut state, &Message::Move { x: 2, y: 2 })
+                .unwrap();
+            assert!(matches!(
+                d.dispatch(&mut state, &Message::Write(String::new())),
+                Err(DispatchError::Failed(_))
+            ));
+        }
+        // the standard handler ran first
+        assert_eq!(moves, vec![Point { x: 1, y: 1 }, Point { x: 2, y: 2 }]);
+    }
+
+    #[test]
+    fn middleware_logs_and_filters() {
+        let mut log = Vec::new();
+        let mut state = State::default();
+        {
+            let mut d = Dispatcher::standard();
+            d.layer(Logger::new(&mut log))
+                .layer(Filter::new(|m: &Message| m.kind() != MessageKind::Write));
+            assert_eq!(
+                d.dispatch(&mut state, &Message::Write(String::from("hi")))
+                    .unwrap(),
+                Outcome::Skipped
+            );
+            d.dispatch(&mut state, &Message::Move { x: 1, y: 2 })
+                .unwrap();
+            d.dispatch(&mut state, &Message::ChangeColor(-1, 0, 0))
+                .unwrap_err();
+        }
+        assert_eq!(state.text, "");
+        assert_eq!(
+            String::from_utf8(log).unwrap(),
+            "write \"hi\": skipped\n\
+             move to (1, 2): ok\n\
+             change color to (-1, 0, 0): channel -1 is not within 0-255\n"
+        );
+    }
+
+    #[test]
+    fn replay_rebuilds_the_state() {
+        let mut recording = Vec::new();
+        let mut live = State::default();
+        {
+            let mut d = Dispatcher::standard();
+            d.layer(Recorder::new(&mut recording));
+            for m in &session()[..4] {
+                d.dispatch(&mut live, m).unwrap();
+            }
+            // failed messages aren't recorded
+            d.dispatch(&mut live, &Message::ChangeColor(0, 0, 999))
+                .unwrap_err();
+        }
+
+        let mut rebuilt = State::default();
+        let handled = Dispatcher::standard()
+            .replay(&mut rebuilt, &recording)
+            .unwrap();
+        assert_eq!(handled, 4);
+        assert_eq!(rebuilt, live);
+
+        assert!(matches!(
+            Dispatcher::standard().replay(&mut State::default(), &recording[..recording.len() - 1]),
+            Err(DispatchError::Wire(WireError::Truncated))
+        ));
+    }
+
+    #[test]
+    fn failed_handlers_leave_the_state_alone() {
+        let mut recording = Vec::new();
+        let mut live = State::default();
+        {
+            let mut d = Dispatcher::standard();
+            d.on(MessageKind::Move, |state, _| {
+                if state.cursor.x > 10 {
+                    Err(DispatchError::Failed(String::from("off the screen")))
+                } else {
+                    Ok(())
+                }
+            });
+            d.layer(Recorder::new(&mut recording));
+            d.dispatch(&mut live, &Message::Move { x: 5, y: 5 })
+                .unwrap();
+            // the standard handler moved the copy before this one failed
+            assert!(matches!(
+                d.dispatch(&mut live, &Message::Move { x: 20, y: 0 }),
+                Err(DispatchError::Failed(_))
+            ));
+        }
+        assert_eq!(live.cursor, Point { x: 5, y: 5 });
+
+        let mut rebuilt = State::default();
+        Dispatcher::standard()
+            .replay(&mut rebuilt, &recording)
+            .unwrap();
+        assert_eq!(rebuilt, live);
+    }
+
+    struct Broken;
+
+    impl io::Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn every_after_runs() {
+        let mut recording = Vec::new();
+        let mut state = State::default();
+        {
+            let mut d = Dispatcher::standard();
+            // afters run in reverse, the Logger fails first
+            d.layer(Recorder::new(&mut recording))
+                .layer(Logger::new(Broken));
+            assert!(matches!(
+                d.dispatch(&mut state, &Message::Write(String::from("hi"))),
+                Err(DispatchError::Io(_))
+            ));
+        }
+        // handled all the same, and recorded
+        assert_eq!(state.text, "hi");
+        let mut rebuilt = State::default();
+        assert_eq!(
+            Dispatcher::standard()
+                .replay(&mut rebuilt, &recording)
+                .unwrap(),
+            1
+        );
+        assert_eq!(rebuilt, state);
+    }
+}
@@ -0,0 +1,473 @@
+// Wire format
+/*
+A binary encoding for Message, one frame per message:
+
+    version: u8 (1) | body length: varint | body
+
+and the body is a tag byte followed by the variant's fields:
+
+    0 Quit
+    1 Move         x: zigzag, y: zigzag
+    2 Write        length: varint, UTF-8 bytes
+    3 ChangeColor  r: zigzag, g: zigzag, b: zigzag
+
+- varints are LEB128: 7 bits per byte, low bits first, high bit set on
+  every byte but the last; overlong ones (a trailing 0x00 byte) are
+  rejected so every message has exactly one encoding
+- zigzag maps signed to unsigned so small negatives stay short:
+  0, -1, 1, -2 .. become 0, 1, 2, 3 ..
+- the body length lets a reader skip a frame it can't decode; a bad
+  header can't be skipped, so the Decoder stops at the first one
+ */
+use std::error::Error;
+use std::fmt;
+use std::str::Utf8Error;
+
+use super::Message;
+
+pub const VERSION: u8 = 1;
+// default limit on the body length the Decoder will buffer
+pub const MAX_FRAME_LEN: usize = 1 << 20;
+
+const QUIT: u8 = 0;
+const MOVE: u8 = 1;
+const WRITE: u8 = 2;
+const CHANGE_COLOR: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    UnsupportedVersion(u8),
+    FrameTooLarge { len: u64, max: usize },
+    // the body ended in the middle of a field
+    Truncated,
+    TrailingBytes(usize),
+    UnknownTag(u8),
+    VarintOverflow,
+    OverlongVarint,
+    InvalidUtf8(Utf8Error),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            WireError::FrameTooLarge { len, max } => {
+                write!(f, "frame of {} bytes is over the {} byte limit", len, max)
+            }
+            WireError::Truncated => write!(f, "frame ends in the middle of a field"),
+            WireError::TrailingBytes(n) => write!(f, "{} bytes left over after the message", n),
+            WireError::UnknownTag(tag) => write!(f, "unknown message tag {}", tag),
+            WireError::VarintOverflow => write!(f, "integer too large"),
+            WireError::OverlongVarint => write!(f, "integer encoded with extra bytes"),
+            WireError::InvalidUtf8(e) => write!(f, "text is not UTF-8: {}", e),
+        }
+    }
+}
+
+impl Error for WireError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WireError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+pub fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+/// The number and how many bytes it took, `Ok(None)` if `bytes` ends first
+pub fn read_varint(bytes: &[u8]) -> Result<Option<(u64, usize)>, WireError> {
+    let mut n: u64 = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        // the tenth byte only has room for the top bit of a u64
+        if i == 9 && byte > 1 || i > 9 {
+            return Err(WireError::VarintOverflow);
+        }
+        n |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(WireError::OverlongVarint);
+            }
+            return Ok(Some((n, i + 1)));
+        }
+    }
+    Ok(None)
+}
+
+pub fn zigzag(n: i32) -> u32 {
+    ((n << 1) ^ (n >> 31)) as u32
+}
+
+pub fn unzigzag(n: u32) -> i32 {
+    (n >> 1) as i32 ^ -((n & 1) as i32)
+}
+
+/// Appends one frame for `message` to `buf`
+pub fn encode_into(message: &Message, buf: &mut Vec<u8>) {
+    let mut body = Vec::new();
+    let int = |body: &mut Vec<u8>, n: i32| write_varint(body, u64::from(zigzag(n)));
+    match message {
+        Message::Quit => body.push(QUIT),
+        Message::Move { x, y } => {
+            body.push(MOVE);
+            int(&mut body, *x);
+            int(&mut body, *y);
+        }
+        Message::Write(text) => {
+            body.push(WRITE);
+            write_varint(&mut body, text.len() as u64);
+            body.extend_from_slice(text.as_bytes());
+        }
+        Message::ChangeColor(r, g, b) => {
+            body.push(CHANGE_COLOR);
+            for &c in &[*r, *g, *b] {
+                int(&mut body, c);
+            }
+        }
+    }
+    buf.push(VERSION);
+    write_varint(buf, body.len() as u64);
+    buf.extend_from_slice(&body);
+}
+
+pub fn encode(message: &Message) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode_into(message, &mut buf);
+    buf
+}
+
+// reads fields off the front of a frame body
+struct Body<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Body<'a> {
+    fn varint(&mut self) -> Result<u64, WireError> {
+        let (n, len) = read_varint(self.bytes)?.ok_or(WireError::Truncated)?;
+        self.bytes = &self.bytes[len..];
+        Ok(n)
+    }
+
+    fn int(&mut self) -> Result<i32, WireError> {
+        let n = self.varint()?;
+        if n > u64::from(u32::MAX) {
+            return Err(WireError::VarintOverflow);
+        }
+        Ok(unzigzag(n as u32))
+    }
+
+    fn take(&mut self, len: u64) -> Result<&'a [u8], WireError> {
+        if len > self.bytes.len() as u64 {
+            return Err(WireError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len as usize);
+        self.bytes = rest;
+        Ok(taken)
+    }
+}
+
+fn decode_body(bytes: &[u8]) -> Result<Message, WireError> {
+    let mut body = Body { bytes };
+    let tag = body.take(1)?[0];
+    let message = match tag {
+        QUIT => Message::Quit,
+        MOVE => Message::Move {
+            x: body.int()?,
+            y: body.int()?,
+        },
+        WRITE => {
+            let len = body.varint()?;
+            let text = std::str::from_utf8(body.take(len)?).map_err(WireError::InvalidUtf8)?;
+            Message::Write(String::from(text))
+        }
+        CHANGE_COLOR => Message::ChangeColor(body.int()?, body.int()?, body.int()?),
+        _ => return Err(WireError::UnknownTag(tag)),
+    };
+    if !body.bytes.is_empty() {
+        return Err(WireError::TrailingBytes(body.bytes.len()));
+    }
+    Ok(message)
+}
+
+// header length and body length, `Ok(None)` until the header is complete
+fn read_header(bytes: &[u8], max: usize) -> Result<Option<(usize, usize)>, WireError> {
+    let version = match bytes.first() {
+        Some(&v) => v,
+        None => return Ok(None),
+    };
+    if version != VERSION {
+        return Err(WireError::UnsupportedVersion(version));
+    }
+    let (len, len_len) = match read_varint(&bytes[1..])? {
+        Some(varint) => varint,
+        None => return Ok(None),
+    };
+    if len > max as u64 {
+        return Err(WireError::FrameTooLarge { len, max });
+    }
+    Ok(Some((1 + len_len, len as usize)))
+}
+
+/// Decodes one whole frame from the start of `bytes`, returning the message
+/// and the frame's length
+pub fn decode(bytes: &[u8]) -> Result<(Message, usize), WireError> {
+    let (header, len) = read_header(bytes, usize::MAX)?.ok_or(WireError::Truncated)?;
+    // a huge length from a corrupt header mustn't overflow
+    let end = header.checked_add(len).ok_or(WireError::Truncated)?;
+    let body = bytes.get(header..end).ok_or(WireError::Truncated)?;
+    Ok((decode_body(body)?, end))
+}
+
+/// Decodes a byte stream that arrives in pieces
+///
+/// `feed` whatever was read, then call `next_message` until it returns
+/// `Ok(None)`, which means it's waiting for more bytes. A frame whose body
+/// doesn't decode is dropped and its error returned, the next call carries
+/// on after it. A bad header leaves no way to find the next frame, so the
+/// Decoder keeps returning that error.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    buf: Vec<u8>,
+    max_frame_len: usize,
+    failed: Option<WireError>,
+}
+
+impl Default for Decoder {
+    fn default() -> Decoder {
+        Decoder::with_max_frame_len(MAX_FRAME_LEN)
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    pub fn with_max_frame_len(max_frame_len: usize) -> Decoder {
+        Decoder {
+            buf: Vec::new(),
+            max_frame_len,
+            failed: None,
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if self.failed.is_none() {
+            self.buf.extend_from_slice(bytes);
+        }
+    }
+
+    /// Bytes waiting for the rest of their frame
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn next_message(&mut self) -> Result<Option<Message>, WireError> {
+        if let Some(e) = &self.failed {
+            return Err(e.clone());
+        }
+        let (header, len) = match read_header(&self.buf, self.max_frame_len) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                self.buf = Vec::new();
+                self.failed = Some(e.clone());
+                return Err(e);
+            }
+        };
+        let end = header.saturating_add(len);
+        if self.buf.len() < end {
+            return Ok(None);
+        }
+        let message = decode_body(&self.buf[header..end]);
+        self.buf.drain(..end);
+        message.map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn message() -> impl Strategy<Value = Message> {
+        prop_oneof![
+            Just(Message::Quit),
+            (any::<i32>(), any::<i32>()).prop_map(|(x, y)| Message::Move { x, y }),
+            any::<String>().prop_map(Message::Write),
+            (any::<i32>(), any::<i32>(), any::<i32>())
+                .prop_map(|(r, g, b)| Message::ChangeColor(r, g, b)),
+        ]
+    }
+
+    // decodes everything, collecting the errors alongside
+    fn drain(decoder: &mut Decoder) -> Vec<Result<Message, WireError>> {
+        let mut out = Vec::new();
+        loop {
+            match decoder.next_message() {
+                Ok(Some(m)) => out.push(Ok(m)),
+                Ok(None) => return out,
+                Err(e) => {
+                    let failed = decoder.failed.is_some();
+                    out.push(Err(e));
+                    if failed {
+                        return out;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn known_encodings() {
+        assert_eq!(encode(&Message::Quit), [1, 1, 0]);
+        assert_eq!(
+            encode(&Message::Move { x: -1, y: 64 }),
+            [1, 4, 1, 1, 0x80, 1]
+        );
+        assert_eq!(
+            encode(&Message::Write(String::from("hé"))),
+            [1, 5, 2, 3, b'h', 0xc3, 0xa9]
+        );
+        assert_eq!(
+            encode(&Message::ChangeColor(0, i32::MAX, i32::MIN)),
+            [1, 12, 3, 0, 0xfe, 0xff, 0xff, 0xff, 0x0f, 0xff, 0xff, 0xff, 0xff, 0x0f]
+        );
+    }
+
+    #[test]
+    fn varints() {
+        for &n in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, n);
+            assert_eq!(read_varint(&buf), Ok(Some((n, buf.len()))));
+            assert_eq!(read_varint(&buf[..buf.len() - 1]), Ok(None));
+        }
+        assert_eq!(read_varint(&[0x80, 0x00]), Err(WireError::OverlongVarint));
+        assert_eq!(read_varint(&[0xff; 10]), Err(WireError::VarintOverflow));
+        let mut too_big = vec![0xff; 9];
+        too_big.push(0x02);
+        assert_eq!(read_varint(&too_big), Err(WireError::VarintOverflow));
+        for &n in &[0, -1, 1, -2, i32::MAX, i32::MIN] {
+            assert_eq!(unzigzag(zigzag(n)), n);
+        }
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+    }
+
+    #[test]
+    fn rejects_malformed_frames() {
+        let cases: &[(&[u8], WireError)] = &[
+            (&[2, 1, 0], WireError::UnsupportedVersion(2)),
+            (&[1, 1, 9], WireError::UnknownTag(9)),
+            (&[1, 2, 0, 0], WireError::TrailingBytes(1)),
+            (&[1, 0], WireError::Truncated),
+            (&[1, 2, 1, 0], WireError::Truncated),
+            (&[1, 3, 2, 5, b'a'], WireError::Truncated),
+            (
+                &[1, 5, 1, 0x80, 0x80, 0x80, 0x00],
+                WireError::OverlongVarint,
+            ),
+            (
+                &[1, 7, 1, 0xff, 0xff, 0xff, 0xff, 0x10, 0],
+                WireError::VarintOverflow,
+            ),
+            (&[1, 3], WireError::Truncated),
+            (
+                &[
+                    1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+                ],
+                WireError::Truncated,
+            ),
+        ];
+        for (bytes, error) in cases {
+            assert_eq!(decode(bytes).err().as_ref(), Some(error), "{:?}", bytes);
+        }
+        assert!(matches!(
+            decode(&[1, 3, 2, 1, 0xff]),
+            Err(WireError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn decoder_skips_bad_bodies_but_stops_at_bad_headers() {
+        let mut decoder = Decoder::new();
+        decoder.feed(&encode(&Message::Quit));
+        decoder.feed(&[1, 1, 9]);
+        decoder.feed(&encode(&Message::Move { x: 1, y: 2 }));
+        assert_eq!(decoder.next_message(), Ok(Some(Message::Quit)));
+        assert_eq!(decoder.next_message(), Err(WireError::UnknownTag(9)));
+        assert_eq!(
+            decoder.next_message(),
+            Ok(Some(Message::Move { x: 1, y: 2 }))
+        );
+        assert_eq!(decoder.next_message(), Ok(None));
+
+        let mut decoder = Decoder::with_max_frame_len(4);
+        decoder.feed(&encode(&Message::Write(String::from("hello"))));
+        let too_large = WireError::FrameTooLarge { len: 7, max: 4 };
+        assert_eq!(decoder.next_message(), Err(too_large.clone()));
+        decoder.feed(&encode(&Message::Quit));
+        assert_eq!(decoder.next_message(), Err(too_large));
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips(m in message()) {
+            let bytes = encode(&m);
+            prop_assert_eq!(decode(&bytes), Ok((m, bytes.len())));
+        }
+
+        #[test]
+        fn decodes_however_the_stream_is_split(
+            messages in prop::collection::vec(message(), 0..8),
+            chunk in 1usize..16,
+        ) {
+            let mut bytes = Vec::new();
+            for m in &messages {
+                encode_into(m, &mut bytes);
+            }
+            let mut decoder = Decoder::new();
+            let mut decoded = Vec::new();
+            for piece in bytes.chunks(chunk) {
+                decoder.feed(piece);
+                decoded.extend(drain(&mut decoder));
+            }
+            prop_assert_eq!(decoded, messages.into_iter().map(Ok).collect::<Vec<_>>());
+            prop_assert_eq!(decoder.buffered(), 0);
+        }
+
+        #[test]
+        fn garbage_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+            if let Ok((m, len)) = decode(&bytes) {
+                // only canonical encodings are accepted
+                prop_assert_eq!(encode(&m), &bytes[..len]);
+            }
+            let mut decoder = Decoder::new();
+            decoder.feed(&bytes);
+            drain(&mut decoder);
+        }
+
+        #[test]
+        fn corrupted_frames_are_rejected_or_canonical(
+            m in message(),
+            index in any::<prop::sample::Index>(),
+            byte in any::<u8>(),
+        ) {
+            let mut bytes = encode(&m);
+            let i = index.index(bytes.len());
+            bytes[i] = byte;
+            if let Ok((decoded, len)) = decode(&bytes) {
+                prop_assert_eq!(encode(&decoded), &bytes[..len]);
+            }
+        }
+    }
+}
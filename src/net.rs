@@ -0,0 +1,479 @@
+// Net
+/*
+A strongly typed IpAddr, grown out of the three IpAddr shapes in enums.rs
+(`enums::enum_values` .. `enums::multiple_components`)
+
+- V4 keeps the four octets, V6 the eight 16-bit groups
+- parsing follows RFC 4291 text form: hex groups, one `::` for a run of
+  zero groups, and a dotted IPv4 address as the last 32 bits
+  (::ffff:192.0.2.1); like std::net, IPv4 parts with leading zeros
+  (010.0.0.1) and zone ids (fe80::1%eth0) are rejected
+- formatting is the RFC 5952 canonical text: lowercase, no leading zeros,
+  the longest run of two or more zero groups as `::` (the first on ties),
+  IPv4-mapped addresses with the dotted tail
+- IPv4-mapped IPv6 addresses are classified as the IPv4 address they carry
+ */
+// CIDR networks, in src/net/cidr.rs
+pub mod cidr;
+// longest-prefix-match routing, in src/net/routing.rs
+pub mod routing;
+
+use std::error::Error;
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrParseError {
+    pub input: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid IP address {:?}: {}", self.input, self.reason)
+    }
+}
+
+impl Error for AddrParseError {}
+
+// IpAddrKind from enums.rs: which version, without the address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddrKind {
+    V4,
+    V6,
+}
+
+impl IpAddrKind {
+    // address length in bits
+    pub fn bits(&self) -> u8 {
+        match self {
+            IpAddrKind::V4 => 32,
+            IpAddrKind::V6 => 128,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddr {
+    V4([u8; 4]),
+    V6([u16; 8]),
+}
+
+impl IpAddr {
+    pub const LOCALHOST_V4: IpAddr = IpAddr::V4([127, 0, 0, 1]);
+    pub const LOCALHOST_V6: IpAddr = IpAddr::V6([0, 0, 0, 0, 0, 0, 0, 1]);
+
+    pub fn kind(&self) -> IpAddrKind {
+        match self {
+            IpAddr::V4(_) => IpAddrKind::V4,
+            IpAddr::V6(_) => IpAddrKind::V6,
+        }
+    }
+
+    /// The address as a number, IPv4 in the low 32 bits
+    pub fn to_bits(&self) -> u128 {
+        match *self {
+            IpAddr::V4(octets) => u128::from(u32::from_be_bytes(octets)),
+            IpAddr::V6(groups) => groups
+                .iter()
+                .fold(0, |bits, &g| (bits << 16) | u128::from(g)),
+        }
+    }
+
+    /// The address of that kind for a number, `None` if it has too many
+    /// bits for an IPv4 one
+    pub fn from_bits(kind: IpAddrKind, bits: u128) -> Option<IpAddr> {
+        use std::convert::TryFrom;
+
+        match kind {
+            IpAddrKind::V4 => Some(IpAddr::V4(u32::try_from(bits).ok()?.to_be_bytes())),
+            IpAddrKind::V6 => {
+                let mut groups = [0u16; 8];
+                for (i, group) in groups.iter_mut().enumerate() {
+                    *group = (bits >> (16 * (7 - i))) as u16;
+                }
+                Some(IpAddr::V6(groups))
+            }
+        }
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        matches!(self, IpAddr::V4(_))
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, IpAddr::V6(_))
+    }
+
+    /// The IPv4 address inside an IPv4-mapped IPv6 one (::ffff:a.b.c.d)
+    pub fn to_ipv4_mapped(&self) -> Option<[u8; 4]> {
+        match *self {
+            IpAddr::V6([0, 0, 0, 0, 0, 0xffff, hi, lo]) => {
+                let [a, b] = hi.to_be_bytes();
+                let [c, d] = lo.to_be_bytes();
+                Some([a, b, c, d])
+            }
+            _ => None,
+        }
+    }
+
+    // classification looks through IPv4-mapped addresses
+    fn unmapped(&self) -> IpAddr {
+        self.to_ipv4_mapped().map_or(*self, IpAddr::V4)
+    }
+
+    /// 0.0.0.0 or ::
+    pub fn is_unspecified(&self) -> bool {
+        match *self {
+            IpAddr::V4(octets) => octets == [0; 4],
+            IpAddr::V6(groups) => groups == [0; 8],
+        }
+    }
+
+    /// 127.0.0.0/8 or ::1
+    pub fn is_loopback(&self) -> bool {
+        match self.unmapped() {
+            IpAddr::V4([a, ..]) => a == 127,
+            IpAddr::V6(groups) => groups == [0, 0, 0, 0, 0, 0, 0, 1],
+        }
+    }
+
+    /// 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16 (RFC 1918) or the IPv6
+    /// unique local fc00::/7 (RFC 4193)
+    pub fn is_private(&self) -> bool {
+        match self.unmapped() {
+            IpAddr::V4([a, b, ..]) => {
+                a == 10 || (a == 172 && (16..=31).contains(&b)) || (a == 192 && b == 168)
+            }
+            IpAddr::V6(groups) => groups[0] & 0xfe00 == 0xfc00,
+        }
+    }
+
+    /// 169.254.0.0/16 or fe80::/10
+    pub fn is_link_local(&self) -> bool {
+        match self.unmapped() {
+            IpAddr::V4([a, b, ..]) => a == 169 && b == 254,
+            IpAddr::V6(groups) => groups[0] & 0xffc0 == 0xfe80,
+        }
+    }
+
+    /// 224.0.0.0/4 or ff00::/8
+    pub fn is_multicast(&self) -> bool {
+        match self.unmapped() {
+            IpAddr::V4([a, ..]) => (224..=239).contains(&a),
+            IpAddr::V6(groups) => groups[0] & 0xff00 == 0xff00,
+        }
+    }
+}
+
+fn parse_v4(s: &str) -> Result<[u8; 4], &'static str> {
+    let mut octets = [0u8; 4];
+    let mut parts = s.split('.');
+    for octet in octets.iter_mut() {
+        let part = parts.next().ok_or("IPv4 needs 4 parts")?;
+        if part.is_empty() || part.len() > 3 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err("IPv4 parts are 1 to 3 digits");
+        }
+        if part.len() > 1 && part.starts_with('0') {
+            return Err("IPv4 parts can't have leading zeros");
+        }
+        *octet = part.parse().map_err(|_| "IPv4 parts are at most 255")?;
+    }
+    if parts.next().is_some() {
+        return Err("IPv4 needs 4 parts");
+    }
+    Ok(octets)
+}
+
+// the groups in one side of a `::`, the last one may be dotted IPv4
+fn parse_groups(s: &str, last: bool, out: &mut Vec<u16>) -> Result<(), &'static str> {
+    if s.is_empty() {
+        return Ok(());
+    }
+    let parts: Vec<&str> = s.split(':').collect();
+    for (i, part) in parts.iter().enumerate() {
+        if last && i == parts.len() - 1 && part.contains('.') {
+            let [a, b, c, d] = parse_v4(part)?;
+            out.push(u16::from_be_bytes([a, b]));
+            out.push(u16::from_be_bytes([c, d]));
+        } else if part.is_empty() || part.len() > 4 {
+            return Err("IPv6 groups are 1 to 4 hex digits");
+        } else {
+            out.push(u16::from_str_radix(part, 16).map_err(|_| "IPv6 groups are hex digits")?);
+        }
+        if out.len() > 8 {
+            return Err("IPv6 has at most 8 groups");
+        }
+    }
+    Ok(())
+}
+
+fn parse_v6(s: &str) -> Result<[u16; 8], &'static str> {
+    // from_str_radix takes a sign, a group can't
+    if !s
+        .bytes()
+        .all(|b| b.is_ascii_hexdigit() || b == b':' || b == b'.')
+    {
+        return Err("unexpected character");
+    }
+    let mut head = Vec::with_capacity(8);
+    let mut tail = Vec::with_capacity(8);
+    match s.find("::") {
+        Some(at) => {
+            let (left, right) = (&s[..at], &s[at + 2..]);
+            if right.contains("::") {
+                return Err("only one :: is allowed");
+            }
+            parse_groups(left, false, &mut head)?;
+            parse_groups(right, true, &mut tail)?;
+            // :: stands for at least one zero group
+            if head.len() + tail.len() > 7 {
+                return Err(":: needs to replace at least one group");
+            }
+        }
+        None => {
+            parse_groups(s, true, &mut head)?;
+            if head.len() != 8 {
+                return Err("IPv6 needs 8 groups or a ::");
+            }
+        }
+    }
+    let mut groups = [0u16; 8];
+    groups[..head.len()].copy_from_slice(&head);
+    groups[8 - tail.len()..].copy_from_slice(&tail);
+    Ok(groups)
+}
+
+impl FromStr for IpAddr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<IpAddr, AddrParseError> {
+        let parsed = if s.contains(':') {
+            parse_v6(s).map(IpAddr::V6)
+        } else {
+            parse_v4(s).map(IpAddr::V4)
+        };
+        parsed.map_err(|reason| AddrParseError {
+            input: String::from(s),
+            reason,
+        })
+    }
+}
+
+impl fmt::Display for IpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let groups = match *self {
+            IpAddr::V4([a, b, c, d]) => return f.pad(&format!("{}.{}.{}.{}", a, b, c, d)),
+            IpAddr::V6(groups) => groups,
+        };
+        if let Some([a, b, c, d]) = self.to_ipv4_mapped() {
+            return f.pad(&format!("::ffff:{}.{}.{}.{}", a, b, c, d));
+        }
+
+        // longest run of zero groups, at least two long
+        let mut best: Option<(usize, usize)> = None;
+        let mut i = 0;
+        while i < 8 {
+            let len = groups[i..].iter().take_while(|&&g| g == 0).count();
+            if len >= 2 && best.is_none_or(|(_, best_len)| len > best_len) {
+                best = Some((i, len));
+            }
+            i += len.max(1);
+        }
+
+        let join = |groups: &[u16]| {
+            groups
+                .iter()
+                .map(|g| format!("{:x}", g))
+                .collect::<Vec<_>>()
+                .join(":")
+        };
+        let text = match best {
+            Some((start, len)) => format!(
+                "{}::{}",
+                join(&groups[..start]),
+                join(&groups[start + len..])
+            ),
+            None => join(&groups),
+        };
+        f.pad(&text)
+    }
+}
+
+impl From<net::Ipv4Addr> for IpAddr {
+    fn from(addr: net::Ipv4Addr) -> IpAddr {
+        IpAddr::V4(addr.octets())
+    }
+}
+
+impl From<net::Ipv6Addr> for IpAddr {
+    fn from(addr: net::Ipv6Addr) -> IpAddr {
+        IpAddr::V6(addr.segments())
+    }
+}
+
+impl From<net::IpAddr> for IpAddr {
+    fn from(addr: net::IpAddr) -> IpAddr {
+        match addr {
+            net::IpAddr::V4(v4) => IpAddr::from(v4),
+            net::IpAddr::V6(v6) => IpAddr::from(v6),
+        }
+    }
+}
+
+impl From<IpAddr> for net::IpAddr {
+    fn from(addr: IpAddr) -> net::IpAddr {
+        match addr {
+            IpAddr::V4(octets) => net::IpAddr::from(octets),
+            IpAddr::V6(groups) => net::IpAddr::from(groups),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v6(s: &str) -> [u16; 8] {
+        match s.parse() {
+            Ok(IpAddr::V6(groups)) => groups,
+            other => panic!("{:?} parsed as {:?}", s, other),
+        }
+    }
+
+    #[test]
+    fn parses_ipv4() {
+        assert_eq!("127.0.0.1".parse(), Ok(IpAddr::LOCALHOST_V4));
+        assert_eq!("255.255.255.255".parse(), Ok(IpAddr::V4([255; 4])));
+        for bad in &[
+            "",
+            "1.2.3",
+            "1.2.3.4.5",
+            "256.0.0.1",
+            "01.2.3.4",
+            "1..2.3",
+            "1.2.3.4 ",
+            "+1.2.3.4",
+        ] {
+            assert!(bad.parse::<IpAddr>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_ipv6() {
+        assert_eq!(v6("::"), [0; 8]);
+        assert_eq!(v6("::1"), [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(v6("1::"), [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            v6("2001:DB8::8:800:200C:417A"),
+            [0x2001, 0xdb8, 0, 0, 8, 0x800, 0x200c, 0x417a]
+        );
+        assert_eq!(v6("1:2:3:4:5:6:7:8"), [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            v6("::ffff:192.0.2.1"),
+            [0, 0, 0, 0, 0, 0xffff, 0xc000, 0x0201]
+        );
+        assert_eq!(
+            v6("1:2:3:4:5:6:1.2.3.4"),
+            [1, 2, 3, 4, 5, 6, 0x0102, 0x0304]
+        );
+        for bad in &[
+            ":",
+            ":::",
+            "1:2:3:4:5:6:7",
+            "1:2:3:4:5:6:7:8:9",
+            "1:2:3:4::5:6:7:8",
+            "1::2::3",
+            ":1::2",
+            "1::2:",
+            "12345::",
+            "g::",
+            "::+1",
+            "1.2.3.4::",
+            "::1.2.3.4:5",
+            "::ffff:1.2.3",
+            "fe80::1%eth0",
+        ] {
+            assert!(bad.parse::<IpAddr>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn formats_canonically() {
+        let canonical = |s: &str| s.parse::<IpAddr>().unwrap().to_string();
+        assert_eq!(
+            canonical("2001:0DB8:0000:0000:0000:0000:0000:0001"),
+            "2001:db8::1"
+        );
+        // a single zero group isn't compressed
+        assert_eq!(canonical("2001:db8:0:1:1:1:1:1"), "2001:db8:0:1:1:1:1:1");
+        // the longest run wins, then the first
+        assert_eq!(canonical("2001:0:0:1:0:0:0:1"), "2001:0:0:1::1");
+        assert_eq!(canonical("2001:db8:0:0:1:0:0:1"), "2001:db8::1:0:0:1");
+        assert_eq!(canonical("::"), "::");
+        assert_eq!(canonical("0:0:0:0:0:ffff:c000:201"), "::ffff:192.0.2.1");
+        assert_eq!(format!("{:>12}", IpAddr::LOCALHOST_V4), "   127.0.0.1");
+    }
+
+    #[test]
+    fn classifies() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(ip("127.1.2.3").is_loopback() && ip("::1").is_loopback());
+        assert!(ip("::ffff:127.0.0.1").is_loopback());
+        assert!(!ip("::2").is_loopback());
+
+        for private in &[
+            "10.0.0.1",
+            "172.16.0.1",
+            "172.31.255.255",
+            "192.168.1.1",
+            "fd00::1",
+        ] {
+            assert!(ip(private).is_private(), "{}", private);
+        }
+        for public in &["172.32.0.1", "8.8.8.8", "2001:db8::1"] {
+            assert!(!ip(public).is_private(), "{}", public);
+        }
+
+        assert!(ip("224.0.0.1").is_multicast() && ip("ff02::1").is_multicast());
+        assert!(!ip("240.0.0.1").is_multicast());
+        assert!(ip("169.254.0.1").is_link_local() && ip("fe80::1").is_link_local());
+        assert!(ip("0.0.0.0").is_unspecified() && ip("::").is_unspecified());
+    }
+
+    #[test]
+    fn converts_to_and_from_bits() {
+        let v4: IpAddr = "192.0.2.1".parse().unwrap();
+        assert_eq!(v4.to_bits(), 0xc000_0201);
+        assert_eq!(IpAddr::from_bits(IpAddrKind::V4, 0xc000_0201), Some(v4));
+        assert_eq!(IpAddr::from_bits(IpAddrKind::V4, 1 << 32), None);
+
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        assert_eq!(v6.to_bits(), 0x2001_0db8_0000_0000_0000_0000_0000_0001);
+        assert_eq!(IpAddr::from_bits(IpAddrKind::V6, v6.to_bits()), Some(v6));
+        assert_eq!(v6.kind(), IpAddrKind::V6);
+    }
+
+    #[test]
+    fn round_trips_with_std() {
+        for s in &[
+            "0.0.0.0",
+            "192.168.0.1",
+            "::",
+            "::1",
+            "2001:db8::1",
+            "2001:db8:0:1:1:1:1:1",
+            "2001:db8:0:0:1:0:0:1",
+            "fe80::1:2:3:4",
+            "::ffff:10.0.0.1",
+            "1:0:0:2:0:0:0:3",
+        ] {
+            let ours: IpAddr = s.parse().unwrap();
+            let std_addr: net::IpAddr = s.parse().unwrap();
+            assert_eq!(net::IpAddr::from(ours), std_addr);
+            assert_eq!(IpAddr::from(std_addr), ours);
+            assert_eq!(ours.to_string(), std_addr.to_string());
+        }
+    }
+}
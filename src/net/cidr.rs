@@ -0,0 +1,480 @@
+// CIDR
+/*
+IP networks in CIDR notation (192.168.0.0/16, 2001:db8::/32), for both
+versions in one type like IpAddr
+
+- an IpNet is always a network address: `new` and parsing reject host
+  bits (192.168.1.1/16), `containing` clears them
+- the arithmetic happens on IpAddr::to_bits, so V4 and V6 share it
+- broadcast is the last address of the network; IPv6 has no broadcast
+  but the last address is still useful
+- hosts skips the network and broadcast addresses for IPv4 up to /30,
+  /31 and /32 have no room for them (RFC 3021); IPv6 keeps every address
+ */
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{AddrParseError, IpAddr, IpAddrKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CidrError {
+    Addr(AddrParseError),
+    MissingPrefix(String),
+    InvalidPrefix(String),
+    PrefixTooLong { prefix: u8, max: u8 },
+    // the new prefix for subnets is shorter than the network's
+    PrefixTooShort { prefix: u8, min: u8 },
+    HostBitsSet { addr: IpAddr, prefix: u8 },
+}
+
+impl fmt::Display for CidrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CidrError::Addr(e) => write!(f, "{}", e),
+            CidrError::MissingPrefix(s) => write!(f, "{:?} has no /prefix", s),
+            CidrError::InvalidPrefix(s) => write!(f, "invalid prefix length {:?}", s),
+            CidrError::PrefixTooLong { prefix, max } => {
+                write!(f, "prefix /{} is longer than /{}", prefix, max)
+            }
+            CidrError::PrefixTooShort { prefix, min } => {
+                write!(f, "prefix /{} is shorter than /{}", prefix, min)
+            }
+            CidrError::HostBitsSet { addr, prefix } => {
+                write!(f, "{}/{} has host bits set", addr, prefix)
+            }
+        }
+    }
+}
+
+impl Error for CidrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CidrError::Addr(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<AddrParseError> for CidrError {
+    fn from(e: AddrParseError) -> CidrError {
+        CidrError::Addr(e)
+    }
+}
+
+// the low `bits` bits set
+fn low_mask(bits: u8) -> u128 {
+    if bits >= 128 {
+        u128::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<IpNet, CidrError> {
+        let net = IpNet::containing(addr, prefix)?;
+        if net.addr != addr {
+            return Err(CidrError::HostBitsSet { addr, prefix });
+        }
+        Ok(net)
+    }
+
+    /// The network of that size `addr` is in
+    pub fn containing(addr: IpAddr, prefix: u8) -> Result<IpNet, CidrError> {
+        let max = addr.kind().bits();
+        if prefix > max {
+            return Err(CidrError::PrefixTooLong { prefix, max });
+        }
+        let bits = addr.to_bits() & !low_mask(max - prefix);
+        Ok(IpNet::from_parts(addr.kind(), bits, prefix))
+    }
+
+    // bits are already a network address of the right size
+    fn from_parts(kind: IpAddrKind, bits: u128, prefix: u8) -> IpNet {
+        IpNet {
+            addr: IpAddr::from_bits(kind, bits).expect("bits fit the address kind"),
+            prefix,
+        }
+    }
+
+    pub fn kind(&self) -> IpAddrKind {
+        self.addr.kind()
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    fn host_bits(&self) -> u8 {
+        self.kind().bits() - self.prefix
+    }
+
+    fn first_bits(&self) -> u128 {
+        self.addr.to_bits()
+    }
+
+    fn last_bits(&self) -> u128 {
+        self.first_bits() | low_mask(self.host_bits())
+    }
+
+    fn addr_of(&self, bits: u128) -> IpAddr {
+        IpAddr::from_bits(self.kind(), bits).expect("bits fit the address kind")
+    }
+
+    pub fn network(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// The last address in the network
+    pub fn broadcast(&self) -> IpAddr {
+        self.addr_of(self.last_bits())
+    }
+
+    /// 255.255.0.0 for a /16
+    pub fn netmask(&self) -> IpAddr {
+        self.addr_of(low_mask(self.kind().bits()) & !low_mask(self.host_bits()))
+    }
+
+    /// 0.0.255.255 for a /16
+    pub fn hostmask(&self) -> IpAddr {
+        self.addr_of(low_mask(self.host_bits()))
+    }
+
+    /// Number of addresses, `None` for ::/0 which has 2^128
+    pub fn size(&self) -> Option<u128> {
+        low_mask(self.host_bits()).checked_add(1)
+    }
+
+    pub fn contains_addr(&self, addr: &IpAddr) -> bool {
+        addr.kind() == self.kind()
+            && (self.first_bits()..=self.last_bits()).contains(&addr.to_bits())
+    }
+
+    /// `other` is this network or one of its subnets
+    pub fn contains(&self, other: &IpNet) -> bool {
+        other.prefix >= self.prefix && self.contains_addr(&other.addr)
+    }
+
+    /// The network one bit shorter, `None` for a /0
+    pub fn supernet(&self) -> Option<IpNet> {
+        let prefix = self.prefix.checked_sub(1)?;
+        IpNet::containing(self.addr, prefix).ok()
+    }
+
+    /// Usable host addresses, see the notes at the top
+    pub fn hosts(&self) -> Hosts {
+        let (mut first, mut last) = (self.first_bits(), self.last_bits());
+        if self.kind() == IpAddrKind::V4 && self.host_bits() >= 2 {
+            first += 1;
+            last -= 1;
+        }
+        Hosts {
+            kind: self.kind(),
+            next: Some(first),
+            last,
+        }
+    }
+
+    /// This network split into networks of length `prefix`, in order
+    pub fn subnets(&self, prefix: u8) -> Result<Subnets, CidrError> {
+        let max = self.kind().bits();
+        if prefix > max {
+            return Err(CidrError::PrefixTooLong { prefix, max });
+        }
+        if prefix < self.prefix {
+            return Err(CidrError::PrefixTooShort {
+                prefix,
+                min: self.prefix,
+            });
+        }
+        Ok(Subnets {
+            kind: self.kind(),
+            prefix,
+            next: Some(self.first_bits()),
+            last: self.last_bits(),
+        })
+    }
+
+    /// The two halves, `None` for a single address
+    pub fn split(&self) -> Option<(IpNet, IpNet)> {
+        let mut halves = self.subnets(self.prefix.checked_add(1)?).ok()?;
+        Some((halves.next()?, halves.next()?))
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("{}/{}", self.addr, self.prefix))
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = CidrError;
+
+    fn from_str(s: &str) -> Result<IpNet, CidrError> {
+        let slash = s
+            .find('/')
+            .ok_or_else(|| CidrError::MissingPrefix(String::from(s)))?;
+        let (addr, prefix) = (&s[..slash], &s[slash + 1..]);
+        // u8::from_str takes a + sign
+        if prefix.is_empty() || !prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(CidrError::InvalidPrefix(String::from(prefix)));
+        }
+        let prefix = prefix
+            .parse()
+            .map_err(|_| CidrError::InvalidPrefix(String::from(prefix)))?;
+        IpNet::new(addr.parse()?, prefix)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Hosts {
+    kind: IpAddrKind,
+    // None once past the last
+    next: Option<u128>,
+    last: u128,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let bits = self.next?;
+        self.next = if bits < self.last {
+            Some(bits + 1)
+        } else {
+            None
+        };
+        IpAddr::from_bits(self.kind, bits)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Subnets {
+    kind: IpAddrKind,
+    prefix: u8,
+    next: Option<u128>,
+    // last address of the network being split
+    last: u128,
+}
+
+impl Iterator for Subnets {
+    type Item = IpNet;
+
+    fn next(&mut self) -> Option<IpNet> {
+        let bits = self.next?;
+        let end = bits | low_mask(self.kind.bits() - self.prefix);
+        self.next = if end < self.last { Some(end + 1) } else { None };
+        Some(IpNet::from_parts(self.kind, bits, self.prefix))
+    }
+}
+
+/// The fewest networks covering exactly the addresses of `nets`
+///
+/// Overlapping and adjacent networks are merged, so 10.0.0.0/25 and
+/// 10.0.0.128/25 become 10.0.0.0/24. V4 networks come first, each version
+/// in address order.
+pub fn aggregate(nets: &[IpNet]) -> Vec<IpNet> {
+    let mut ranges: Vec<(IpAddrKind, u128, u128)> = nets
+        .iter()
+        .map(|n| (n.kind(), n.first_bits(), n.last_bits()))
+        .collect();
+    ranges.sort_unstable();
+
+    // merge into disjoint, non-adjacent ranges
+    let mut merged: Vec<(IpAddrKind, u128, u128)> = Vec::new();
+    for (kind, first, last) in ranges {
+        match merged.last_mut() {
+            Some((k, _, end)) if *k == kind && first <= end.saturating_add(1) => {
+                *end = (*end).max(last);
+            }
+            _ => merged.push((kind, first, last)),
+        }
+    }
+
+    let mut out = Vec::new();
+    for (kind, mut first, last) in merged {
+        let width = kind.bits();
+        loop {
+            // the biggest block that starts aligned at `first` and fits
+            let aligned = if first == 0 {
+                width
+            } else {
+                (first.trailing_zeros() as u8).min(width)
+            };
+            let host_bits = (0..=aligned)
+                .rev()
+                .find(|&h| low_mask(h) <= last - first)
+                .unwrap_or(0);
+            out.push(IpNet::from_parts(kind, first, width - host_bits));
+            let end = first | low_mask(host_bits);
+            if end >= last {
+                break;
+            }
+            first = end + 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> IpNet {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_formats() {
+        assert_eq!(net("10.0.0.0/8").to_string(), "10.0.0.0/8");
+        assert_eq!(net("2001:DB8::/32").to_string(), "2001:db8::/32");
+        assert_eq!(net("0.0.0.0/0").prefix(), 0);
+
+        assert_eq!(
+            "10.0.0.1/8".parse::<IpNet>(),
+            Err(CidrError::HostBitsSet {
+                addr: ip("10.0.0.1"),
+                prefix: 8
+            })
+        );
+        assert_eq!(
+            "10.0.0.0/33".parse::<IpNet>(),
+            Err(CidrError::PrefixTooLong {
+                prefix: 33,
+                max: 32
+            })
+        );
+        assert!(matches!(
+            "10.0.0.0".parse::<IpNet>(),
+            Err(CidrError::MissingPrefix(_))
+        ));
+        for bad in &["10.0.0.0/", "10.0.0.0/+8", "10.0.0.0/x", "10.0.0.0/256"] {
+            assert!(matches!(
+                bad.parse::<IpNet>(),
+                Err(CidrError::InvalidPrefix(_))
+            ));
+        }
+        assert!(matches!(
+            "10.0.0/8".parse::<IpNet>(),
+            Err(CidrError::Addr(_))
+        ));
+        assert_eq!(
+            IpNet::containing(ip("192.168.1.77"), 24),
+            Ok(net("192.168.1.0/24"))
+        );
+    }
+
+    #[test]
+    fn addresses_and_masks() {
+        let n = net("192.168.0.0/22");
+        assert_eq!(n.network(), ip("192.168.0.0"));
+        assert_eq!(n.broadcast(), ip("192.168.3.255"));
+        assert_eq!(n.netmask(), ip("255.255.252.0"));
+        assert_eq!(n.hostmask(), ip("0.0.3.255"));
+        assert_eq!(n.size(), Some(1024));
+
+        let v6 = net("2001:db8::/32");
+        assert_eq!(v6.broadcast(), ip("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"));
+        assert_eq!(v6.netmask(), ip("ffff:ffff::"));
+        assert_eq!(net("::/0").size(), None);
+        assert_eq!(net("0.0.0.0/0").size(), Some(1 << 32));
+    }
+
+    #[test]
+    fn containment() {
+        let n = net("10.1.0.0/16");
+        assert!(n.contains_addr(&ip("10.1.255.255")));
+        assert!(!n.contains_addr(&ip("10.2.0.0")));
+        assert!(!n.contains_addr(&ip("::ffff:10.1.0.1")));
+        assert!(n.contains(&net("10.1.2.0/24")));
+        assert!(n.contains(&n));
+        assert!(!n.contains(&net("10.0.0.0/8")));
+        assert!(net("::/0").contains_addr(&ip("ffff::1")));
+    }
+
+    #[test]
+    fn hosts_skip_network_and_broadcast() {
+        let hosts: Vec<IpAddr> = net("192.168.1.0/30").hosts().collect();
+        assert_eq!(hosts, vec![ip("192.168.1.1"), ip("192.168.1.2")]);
+        assert_eq!(net("192.168.1.0/31").hosts().count(), 2);
+        assert_eq!(
+            net("192.168.1.7/32").hosts().collect::<Vec<_>>(),
+            vec![ip("192.168.1.7")]
+        );
+        assert_eq!(net("10.0.0.0/24").hosts().count(), 254);
+        // the very last address doesn't overflow
+        assert_eq!(net("ffff::fffe/127").hosts().count(), 2);
+        assert_eq!(
+            net("255.255.255.252/30").hosts().last(),
+            Some(ip("255.255.255.254"))
+        );
+    }
+
+    #[test]
+    fn splits_and_joins() {
+        let n = net("10.0.0.0/24");
+        let quarters: Vec<IpNet> = n.subnets(26).unwrap().collect();
+        assert_eq!(
+            quarters,
+            vec![
+                net("10.0.0.0/26"),
+                net("10.0.0.64/26"),
+                net("10.0.0.128/26"),
+                net("10.0.0.192/26")
+            ]
+        );
+        assert_eq!(n.subnets(24).unwrap().collect::<Vec<_>>(), vec![n]);
+        assert_eq!(
+            n.subnets(16).err(),
+            Some(CidrError::PrefixTooShort {
+                prefix: 16,
+                min: 24
+            })
+        );
+        assert_eq!(net("::/0").subnets(1).unwrap().count(), 2);
+
+        assert_eq!(n.split(), Some((net("10.0.0.0/25"), net("10.0.0.128/25"))));
+        assert_eq!(net("10.0.0.1/32").split(), None);
+        assert_eq!(net("10.0.0.128/25").supernet(), Some(n));
+        assert_eq!(net("0.0.0.0/0").supernet(), None);
+    }
+
+    #[test]
+    fn aggregates() {
+        let nets = [
+            net("10.0.0.128/25"),
+            net("2001:db8::/33"),
+            net("10.0.0.0/25"),
+            net("10.0.1.0/24"),
+            net("10.0.0.64/26"),
+            net("10.0.3.0/24"),
+            net("2001:db8:8000::/33"),
+        ];
+        assert_eq!(
+            aggregate(&nets),
+            vec![net("10.0.0.0/23"), net("10.0.3.0/24"), net("2001:db8::/32")]
+        );
+        // an unaligned range takes several blocks
+        assert_eq!(
+            aggregate(&[net("10.0.0.1/32"), net("10.0.0.2/31"), net("10.0.0.4/32")]),
+            vec![net("10.0.0.1/32"), net("10.0.0.2/31"), net("10.0.0.4/32")]
+        );
+        assert_eq!(
+            aggregate(&[net("::/1"), net("8000::/1")]),
+            vec![net("::/0")]
+        );
+        assert_eq!(aggregate(&[]), vec![]);
+    }
+}
@@ -0,0 +1,129 @@
+// Routing
+/*
+The table `route(ip_kind: IpAddrKind)` in `enums::enum_values` never had
+
+- maps networks to whatever a route leads to (a next hop, an interface..)
+- lookup picks the longest prefix containing the address: with 10.0.0.0/8
+  and 10.1.0.0/16, 10.1.2.3 goes to the /16
+- it tries each prefix length from the longest down, so a lookup is at
+  most 33 (V4) or 129 (V6) map lookups however many routes there are
+ */
+use std::collections::BTreeMap;
+
+use super::cidr::IpNet;
+use super::{IpAddr, IpAddrKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingTable<T> {
+    routes: BTreeMap<IpNet, T>,
+}
+
+impl<T> Default for RoutingTable<T> {
+    fn default() -> RoutingTable<T> {
+        RoutingTable {
+            routes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> RoutingTable<T> {
+    pub fn new() -> RoutingTable<T> {
+        RoutingTable::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Adds a route, handing back the one it replaces
+    pub fn insert(&mut self, net: IpNet, target: T) -> Option<T> {
+        self.routes.insert(net, target)
+    }
+
+    pub fn remove(&mut self, net: &IpNet) -> Option<T> {
+        self.routes.remove(net)
+    }
+
+    /// The most specific route for `addr`
+    pub fn lookup(&self, addr: &IpAddr) -> Option<(&IpNet, &T)> {
+        (0..=addr.kind().bits()).rev().find_map(|prefix| {
+            let net = IpNet::containing(*addr, prefix).ok()?;
+            self.routes.get_key_value(&net)
+        })
+    }
+
+    /// Every route for one IP version, in address order
+    pub fn routes(&self, kind: IpAddrKind) -> impl Iterator<Item = (&IpNet, &T)> {
+        self.routes
+            .iter()
+            .filter(move |(net, _)| net.kind() == kind)
+    }
+
+    /// The /0 route for one IP version, if there is one
+    pub fn default_route(&self, kind: IpAddrKind) -> Option<&T> {
+        let any = match kind {
+            IpAddrKind::V4 => IpAddr::V4([0; 4]),
+            IpAddrKind::V6 => IpAddr::V6([0; 8]),
+        };
+        self.routes.get(&IpNet::containing(any, 0).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> RoutingTable<&'static str> {
+        let mut t = RoutingTable::new();
+        for (net, hop) in &[
+            ("0.0.0.0/0", "upstream"),
+            ("10.0.0.0/8", "core"),
+            ("10.1.0.0/16", "office"),
+            ("10.1.2.0/24", "lab"),
+            ("2001:db8::/32", "v6-core"),
+        ] {
+            t.insert(net.parse().unwrap(), *hop);
+        }
+        t
+    }
+
+    fn hop<'a>(t: &'a RoutingTable<&'static str>, addr: &str) -> Option<&'a str> {
+        t.lookup(&addr.parse().unwrap()).map(|(_, hop)| *hop)
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let t = table();
+        assert_eq!(hop(&t, "10.1.2.3"), Some("lab"));
+        assert_eq!(hop(&t, "10.1.3.3"), Some("office"));
+        assert_eq!(hop(&t, "10.200.0.1"), Some("core"));
+        assert_eq!(hop(&t, "8.8.8.8"), Some("upstream"));
+        assert_eq!(hop(&t, "2001:db8::1"), Some("v6-core"));
+        // no V6 default route
+        assert_eq!(hop(&t, "2001:db9::1"), None);
+    }
+
+    #[test]
+    fn insert_replace_and_remove() {
+        let mut t = table();
+        let lab = "10.1.2.0/24".parse().unwrap();
+        assert_eq!(t.insert(lab, "lab2"), Some("lab"));
+        assert_eq!(hop(&t, "10.1.2.3"), Some("lab2"));
+        assert_eq!(t.remove(&lab), Some("lab2"));
+        assert_eq!(hop(&t, "10.1.2.3"), Some("office"));
+        assert_eq!(t.len(), 4);
+    }
+
+    #[test]
+    fn routes_by_kind() {
+        let t = table();
+        assert_eq!(t.routes(IpAddrKind::V4).count(), 4);
+        assert_eq!(t.routes(IpAddrKind::V6).count(), 1);
+        assert_eq!(t.default_route(IpAddrKind::V4), Some(&"upstream"));
+        assert_eq!(t.default_route(IpAddrKind::V6), None);
+    }
+}
@@ -0,0 +1,399 @@
+// Rules
+/*
+The `match some_u8_value { 1 => .., 3 => .., _ => () }` from
+`enums::placeholder` as a table built at runtime, e.g. from a config file
+
+- a pattern is an exact value, an inclusive range, a set of values, a
+  named predicate (even, odd) or `_` for anything
+- first-match mode acts like `match`, the first rule that fits wins;
+  all-match mode returns every rule that fits, in order
+- u8 has only 256 values, so coverage is worked out by trying every one:
+  `uncovered` is what a `match` without `_` would complain about, and
+  `unreachable` lists rules an earlier rule already covers completely
+- the config text uses match syntax, one `pattern => action` per line:
+      1 | 3 | 5 | 7 => odd digit
+      10..=19 => teen
+      even => even
+      _ => other
+ */
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    InvalidNumber(String),
+    // start > end
+    EmptyRange(u8, u8),
+    UnknownPredicate(String),
+    MissingArrow(String),
+    AtLine { line: usize, error: Box<RuleError> },
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::InvalidNumber(s) => write!(f, "{:?} is not a number from 0 to 255", s),
+            RuleError::EmptyRange(start, end) => write!(f, "range {}..={} is empty", start, end),
+            RuleError::UnknownPredicate(s) => write!(f, "unknown pattern {:?}", s),
+            RuleError::MissingArrow(s) => write!(f, "expected `pattern => action`, got {:?}", s),
+            // the error itself is the source
+            RuleError::AtLine { line, .. } => write!(f, "invalid rule on line {}", line),
+        }
+    }
+}
+
+impl Error for RuleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RuleError::AtLine { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+pub enum Pattern {
+    Exact(u8),
+    Range(RangeInclusive<u8>),
+    Set(Vec<u8>),
+    Predicate {
+        name: String,
+        test: Box<dyn Fn(u8) -> bool>,
+    },
+    Any,
+}
+
+impl Pattern {
+    pub fn predicate<F: Fn(u8) -> bool + 'static>(name: &str, test: F) -> Pattern {
+        Pattern::Predicate {
+            name: String::from(name),
+            test: Box::new(test),
+        }
+    }
+
+    pub fn matches(&self, value: u8) -> bool {
+        match self {
+            Pattern::Exact(v) => *v == value,
+            Pattern::Range(range) => range.contains(&value),
+            Pattern::Set(values) => values.contains(&value),
+            Pattern::Predicate { test, .. } => test(value),
+            Pattern::Any => true,
+        }
+    }
+
+    fn values(&self) -> ValueSet {
+        let mut set = ValueSet::default();
+        for v in (0..=u8::MAX).filter(|&v| self.matches(v)) {
+            set.insert(v);
+        }
+        set
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Pattern({})", self)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Exact(v) => write!(f, "{}", v),
+            Pattern::Range(range) => write!(f, "{}..={}", range.start(), range.end()),
+            Pattern::Set(values) => {
+                let values: Vec<String> = values.iter().map(u8::to_string).collect();
+                write!(f, "{}", values.join(" | "))
+            }
+            Pattern::Predicate { name, .. } => write!(f, "{}", name),
+            Pattern::Any => write!(f, "_"),
+        }
+    }
+}
+
+fn number(s: &str) -> Result<u8, RuleError> {
+    s.trim()
+        .parse()
+        .map_err(|_| RuleError::InvalidNumber(String::from(s.trim())))
+}
+
+impl FromStr for Pattern {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Pattern, RuleError> {
+        let s = s.trim();
+        if s.contains('|') {
+            let values = s.split('|').map(number).collect::<Result<_, _>>()?;
+            return Ok(Pattern::Set(values));
+        }
+        if let Some(dots) = s.find("..=") {
+            let (start, end) = (number(&s[..dots])?, number(&s[dots + 3..])?);
+            if start > end {
+                return Err(RuleError::EmptyRange(start, end));
+            }
+            return Ok(Pattern::Range(start..=end));
+        }
+        match s {
+            "_" => Ok(Pattern::Any),
+            "even" => Ok(Pattern::predicate("even", |v| v % 2 == 0)),
+            "odd" => Ok(Pattern::predicate("odd", |v| v % 2 == 1)),
+            _ if s.bytes().all(|b| b.is_ascii_digit()) => Ok(Pattern::Exact(number(s)?)),
+            _ => Err(RuleError::UnknownPredicate(String::from(s))),
+        }
+    }
+}
+
+// a set of u8 values, one bit each
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct ValueSet([u64; 4]);
+
+impl ValueSet {
+    fn insert(&mut self, v: u8) {
+        self.0[v as usize / 64] |= 1 << (v % 64);
+    }
+
+    fn contains(&self, v: u8) -> bool {
+        self.0[v as usize / 64] & (1 << (v % 64)) != 0
+    }
+
+    fn union(&self, other: &ValueSet) -> ValueSet {
+        let mut out = *self;
+        for (word, o) in out.0.iter_mut().zip(&other.0) {
+            *word |= o;
+        }
+        out
+    }
+
+    fn is_subset(&self, other: &ValueSet) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a & !b == 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    First,
+    All,
+}
+
+#[derive(Debug)]
+pub struct Rule<A> {
+    pub pattern: Pattern,
+    pub action: A,
+}
+
+#[derive(Debug)]
+pub struct RuleTable<A> {
+    rules: Vec<Rule<A>>,
+    mode: MatchMode,
+}
+
+impl<A> RuleTable<A> {
+    pub fn new(mode: MatchMode) -> RuleTable<A> {
+        RuleTable {
+            rules: Vec::new(),
+            mode,
+        }
+    }
+
+    pub fn mode(&self) -> MatchMode {
+        self.mode
+    }
+
+    pub fn rules(&self) -> &[Rule<A>] {
+        &self.rules
+    }
+
+    pub fn add(&mut self, pattern: Pattern, action: A) -> &mut RuleTable<A> {
+        self.rules.push(Rule { pattern, action });
+        self
+    }
+
+    /// The action of the first rule `value` matches
+    pub fn first(&self, value: u8) -> Option<&A> {
+        self.rules
+            .iter()
+            .find(|r| r.pattern.matches(value))
+            .map(|r| &r.action)
+    }
+
+    /// Every action `value` matches, in rule order
+    pub fn all(&self, value: u8) -> impl Iterator<Item = &A> {
+        self.rules
+            .iter()
+            .filter(move |r| r.pattern.matches(value))
+            .map(|r| &r.action)
+    }
+
+    /// The actions for `value` according to the table's mode
+    pub fn dispatch(&self, value: u8) -> Vec<&A> {
+        match self.mode {
+            MatchMode::First => self.first(value).into_iter().collect(),
+            MatchMode::All => self.all(value).collect(),
+        }
+    }
+
+    /// The values no rule matches, as ranges
+    pub fn uncovered(&self) -> Vec<RangeInclusive<u8>> {
+        let covered = self
+            .rules
+            .iter()
+            .fold(ValueSet::default(), |set, r| set.union(&r.pattern.values()));
+        let mut ranges: Vec<RangeInclusive<u8>> = Vec::new();
+        for v in (0..=u8::MAX).filter(|&v| !covered.contains(v)) {
+            match ranges.last_mut() {
+                Some(last) if *last.end() as u16 + 1 == v as u16 => *last = *last.start()..=v,
+                _ => ranges.push(v..=v),
+            }
+        }
+        ranges
+    }
+
+    pub fn is_exhaustive(&self) -> bool {
+        self.uncovered().is_empty()
+    }
+
+    /// Indexes of the rules that can never be first to match, because
+    /// the rules before them cover all their values
+    pub fn unreachable(&self) -> Vec<usize> {
+        let mut before = ValueSet::default();
+        let mut out = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            let values = rule.pattern.values();
+            if values.is_subset(&before) {
+                out.push(i);
+            }
+            before = before.union(&values);
+        }
+        out
+    }
+}
+
+impl RuleTable<String> {
+    /// A table from `pattern => action` lines, see the notes at the top
+    pub fn parse(text: &str, mode: MatchMode) -> Result<RuleTable<String>, RuleError> {
+        let mut table = RuleTable::new(mode);
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let rule = parse_rule(line).map_err(|error| RuleError::AtLine {
+                line: i + 1,
+                error: Box::new(error),
+            })?;
+            table.rules.push(rule);
+        }
+        Ok(table)
+    }
+}
+
+fn parse_rule(line: &str) -> Result<Rule<String>, RuleError> {
+    let arrow = line
+        .find("=>")
+        .ok_or_else(|| RuleError::MissingArrow(String::from(line)))?;
+    Ok(Rule {
+        pattern: line[..arrow].parse()?,
+        action: String::from(line[arrow + 2..].trim()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+        # the digits from enums::placeholder
+        1 | 3 | 5 | 7 => odd digit
+        10..=19 => teen
+        even => even
+    ";
+
+    #[test]
+    fn first_and_all_match() {
+        let table = RuleTable::parse(CONFIG, MatchMode::First).unwrap();
+        assert_eq!(table.dispatch(3), vec!["odd digit"]);
+        assert_eq!(table.dispatch(12), vec!["teen"]);
+        assert_eq!(table.dispatch(200), vec!["even"]);
+        assert!(table.dispatch(9).is_empty());
+
+        let table = RuleTable::parse(CONFIG, MatchMode::All).unwrap();
+        assert_eq!(table.dispatch(12), vec!["teen", "even"]);
+        assert_eq!(table.dispatch(11), vec!["teen"]);
+        assert_eq!(table.first(12).map(String::as_str), Some("teen"));
+    }
+
+    #[test]
+    fn reports_uncovered_values() {
+        let mut table = RuleTable::new(MatchMode::First);
+        table
+            .add(Pattern::Range(0..=9), "digit")
+            .add(Pattern::predicate("big", |v| v >= 200), "big")
+            .add(Pattern::Set(vec![12, 13]), "unlucky");
+        assert_eq!(table.uncovered(), vec![10..=11, 14..=199]);
+        assert!(!table.is_exhaustive());
+
+        table.add(Pattern::Any, "rest");
+        assert!(table.is_exhaustive());
+
+        let odd = RuleTable::parse("odd => odd", MatchMode::All).unwrap();
+        assert_eq!(odd.uncovered().len(), 128);
+        assert_eq!(odd.uncovered()[0], 0..=0);
+        assert_eq!(
+            RuleTable::<()>::new(MatchMode::All).uncovered(),
+            vec![0..=255]
+        );
+    }
+
+    #[test]
+    fn finds_unreachable_rules() {
+        let table = RuleTable::parse(
+            "0..=127 => low
+             5 => five
+             128..=255 => high
+             even => even
+             _ => other",
+            MatchMode::First,
+        )
+        .unwrap();
+        assert_eq!(table.unreachable(), vec![1, 3, 4]);
+        assert!(RuleTable::parse(CONFIG, MatchMode::First)
+            .unwrap()
+            .unreachable()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("256 => x", RuleError::InvalidNumber(String::from("256"))),
+            ("9..=3 => x", RuleError::EmptyRange(9, 3)),
+            (
+                "prime => x",
+                RuleError::UnknownPredicate(String::from("prime")),
+            ),
+            ("1 | a => x", RuleError::InvalidNumber(String::from("a"))),
+            ("7 -> x", RuleError::MissingArrow(String::from("7 -> x"))),
+        ];
+        for (line, error) in cases.iter() {
+            let text = format!("_ => ok\n{}", line);
+            let e = RuleTable::parse(&text, MatchMode::First).unwrap_err();
+            assert_eq!(
+                e,
+                RuleError::AtLine {
+                    line: 2,
+                    error: Box::new(error.clone())
+                }
+            );
+            assert_eq!(e.to_string(), "invalid rule on line 2");
+            assert_eq!(e.source().unwrap().to_string(), error.to_string());
+        }
+    }
+
+    #[test]
+    fn patterns_display_as_parsed() {
+        for text in &["7", "3..=9", "1 | 3 | 5", "even", "_"] {
+            assert_eq!(text.parse::<Pattern>().unwrap().to_string(), *text);
+        }
+    }
+}
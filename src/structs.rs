@@ -9,6 +9,7 @@
 // methods: allow behaviour of structs
 // associated functions: namespace functionality without instance
 
+// users::User and users::UserStore are the full version
 pub fn basic_struct() {
     // keyword: User - names the entire struct
     struct User {
@@ -55,6 +56,7 @@ pub fn basic_struct() {
     };
 }
 
+// color::Color and geometry::vector::Vec3 are the full versions of these
 pub fn tuple_struct_simple() {
     // struct have looks similar to tuples (fields don't have names)
     struct Color(i32, i32, i32);
@@ -69,6 +71,7 @@ pub fn tuple_struct_simple() {
     let black_part = black.0;
 }
 
+// users::UserRef is the borrowed version, with the lifetime specifiers
 pub fn ownership_struct() {
     // String type means instance of struct owns all the data
     // - all the data wil be valid as long as the entire struct is valid
@@ -88,6 +91,7 @@ pub fn ownership_struct() {
     // };
 }
 
+// geometry::Rectangle is the full version used outside these examples
 pub fn rectangles() {
     let width1 = 30;
     let height1 = 50;
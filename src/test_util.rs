@@ -0,0 +1,36 @@
+// Test helpers
+/*
+Shared by the unit tests in the library crate, only built for `cargo test`
+ */
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, removed on drop so it goes
+/// even when an assert fails; `name` keeps tests running at the same time
+/// apart
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> TempDir {
+        let dir =
+            std::env::temp_dir().join(format!("rust-example-{}-{}", name, std::process::id()));
+        // left over from a run that was killed
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub(crate) fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,545 @@
+// Users
+/*
+User accounts, grown out of `structs::basic_struct`
+
+- Email and Username are newtypes: the only way to get one is through
+  parse, so a User always holds valid, normalized values
+- both are lowercased, so "Someone@Example.com" and "someone@example.com"
+  are the same account
+- UserStore keeps both unique and hands out &User only, changes go through
+  its methods
+- every failure is a UserError, nothing panics
+- UserRef is the borrowed version the struct chapter couldn't have without
+  lifetimes: it points into the text it was parsed from, so bulk imports
+  only allocate for the users that are kept (`to_owned`)
+ */
+// reading a username from a file on a search path, in src/users/source.rs
+pub mod source;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserError {
+    InvalidEmail {
+        email: String,
+        reason: &'static str,
+    },
+    InvalidUsername {
+        username: String,
+        reason: &'static str,
+    },
+    EmailTaken(Email),
+    UsernameTaken(Username),
+    // no user with this email or username
+    UnknownUser(String),
+    Inactive(Username),
+    // a CSV record without the expected fields
+    InvalidRecord(&'static str),
+    // where in a CSV import the error happened, 1-based
+    AtLine {
+        line: usize,
+        error: Box<UserError>,
+    },
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserError::InvalidEmail { email, reason } => {
+                write!(f, "invalid email {:?}: {}", email, reason)
+            }
+            UserError::InvalidUsername { username, reason } => {
+                write!(f, "invalid username {:?}: {}", username, reason)
+            }
+            UserError::EmailTaken(email) => write!(f, "email {} is already registered", email),
+            UserError::UsernameTaken(username) => {
+                write!(f, "username {} is already taken", username)
+            }
+            UserError::UnknownUser(login) => write!(f, "no user {:?}", login),
+            UserError::Inactive(username) => write!(f, "user {} is deactivated", username),
+            UserError::InvalidRecord(reason) => write!(f, "invalid record: {}", reason),
+            // the error itself is the source
+            UserError::AtLine { line, .. } => write!(f, "invalid user on line {}", line),
+        }
+    }
+}
+
+impl Error for UserError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UserError::AtLine { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn check_email(email: &str) -> Result<(), &'static str> {
+    if email.len() > 254 {
+        return Err("longer than 254 characters");
+    }
+    if email.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("contains whitespace");
+    }
+    let (local, domain) = match email.find('@') {
+        Some(at) => (&email[..at], &email[at + 1..]),
+        None => return Err("missing @"),
+    };
+    if domain.contains('@') {
+        return Err("more than one @");
+    }
+    if local.is_empty() {
+        return Err("nothing before the @");
+    }
+    if domain.split('.').count() < 2 || domain.split('.').any(str::is_empty) {
+        return Err("domain needs dot-separated parts, like example.com");
+    }
+    Ok(())
+}
+
+pub(crate) fn check_username(username: &str) -> Result<(), &'static str> {
+    let len = username.chars().count();
+    if !(3..=32).contains(&len) {
+        return Err("must be 3 to 32 characters");
+    }
+    if !username.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err("must start with a letter");
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err("only letters, digits, _ and - are allowed");
+    }
+    Ok(())
+}
+
+/// A validated email address, lowercased
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Email(String);
+
+impl Email {
+    pub fn parse(email: &str) -> Result<Email, UserError> {
+        let email = email.trim();
+        check_email(email).map_err(|reason| UserError::InvalidEmail {
+            email: String::from(email),
+            reason,
+        })?;
+        Ok(Email(email.to_lowercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A validated username: 3 to 32 ASCII letters, digits, `_` or `-`,
+/// starting with a letter, lowercased
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Username(String);
+
+impl Username {
+    pub fn parse(username: &str) -> Result<Username, UserError> {
+        let username = username.trim();
+        check_username(username).map_err(|reason| UserError::InvalidUsername {
+            username: String::from(username),
+            reason,
+        })?;
+        Ok(Username(username.to_ascii_lowercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub username: Username,
+    pub email: Email,
+    pub sign_in_count: u64,
+    pub active: bool,
+}
+
+impl User {
+    // build_user from the chapter, minus the sign-in: registering isn't one
+    pub fn new(email: Email, username: Username) -> User {
+        User {
+            username,
+            email,
+            sign_in_count: 0,
+            active: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UserStore {
+    users: Vec<User>,
+    // positions in `users`, which only ever grows
+    by_email: HashMap<Email, usize>,
+    by_username: HashMap<Username, usize>,
+}
+
+impl UserStore {
+    pub fn new() -> UserStore {
+        UserStore::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &User> {
+        self.users.iter()
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &User> {
+        self.users.iter().filter(|u| u.active)
+    }
+
+    pub fn register(&mut self, email: &str, username: &str) -> Result<&User, UserError> {
+        self.insert(User::new(Email::parse(email)?, Username::parse(username)?))
+    }
+
+    /// Adds an already built user, keeping its count and active flag
+    pub fn insert(&mut self, user: User) -> Result<&User, UserError> {
+        if self.by_email.contains_key(&user.email) {
+            return Err(UserError::EmailTaken(user.email));
+        }
+        if self.by_username.contains_key(&user.username) {
+            return Err(UserError::UsernameTaken(user.username));
+        }
+        let idx = self.users.len();
+        self.by_email.insert(user.email.clone(), idx);
+        self.by_username.insert(user.username.clone(), idx);
+        self.users.push(user);
+        Ok(&self.users[idx])
+    }
+
+    // anything that doesn't parse can't be registered either
+    pub fn by_email(&self, email: &str) -> Option<&User> {
+        let email = Email::parse(email).ok()?;
+        self.by_email.get(&email).map(|&idx| &self.users[idx])
+    }
+
+    pub fn by_username(&self, username: &str) -> Option<&User> {
+        let username = Username::parse(username).ok()?;
+        self.by_username.get(&username).map(|&idx| &self.users[idx])
+    }
+
+    // an email has an @, a username can't
+    fn find(&self, login: &str) -> Result<usize, UserError> {
+        let idx = if login.contains('@') {
+            Email::parse(login)
+                .ok()
+                .and_then(|e| self.by_email.get(&e).copied())
+        } else {
+            Username::parse(login)
+                .ok()
+                .and_then(|u| self.by_username.get(&u).copied())
+        };
+        idx.ok_or_else(|| UserError::UnknownUser(String::from(login)))
+    }
+
+    /// Records a sign-in by email or username
+    pub fn sign_in(&mut self, login: &str) -> Result<&User, UserError> {
+        let idx = self.find(login)?;
+        let user = &mut self.users[idx];
+        if !user.active {
+            return Err(UserError::Inactive(user.username.clone()));
+        }
+        user.sign_in_count += 1;
+        Ok(user)
+    }
+
+    /// Deactivated users keep their email and username but can't sign in
+    pub fn deactivate(&mut self, login: &str) -> Result<&User, UserError> {
+        self.set_active(login, false)
+    }
+
+    pub fn reactivate(&mut self, login: &str) -> Result<&User, UserError> {
+        self.set_active(login, true)
+    }
+
+    fn set_active(&mut self, login: &str, active: bool) -> Result<&User, UserError> {
+        let idx = self.find(login)?;
+        let user = &mut self.users[idx];
+        user.active = active;
+        Ok(user)
+    }
+}
+
+pub const CSV_HEADER: &str = "username,email,sign_in_count,active";
+
+/// A user borrowing its strings from the text it was parsed from
+///
+/// The fields are checked like Email and Username but not normalized, that
+/// would need a copy; `to_owned` does it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserRef<'a> {
+    username: &'a str,
+    email: &'a str,
+    sign_in_count: u64,
+    active: bool,
+}
+
+impl<'a> UserRef<'a> {
+    /// Parses one `username,email,sign_in_count,active` record
+    ///
+    /// Fields are trimmed; quoting isn't supported, none of the fields can
+    /// hold a comma or a quote anyway.
+    pub fn parse_csv(record: &'a str) -> Result<UserRef<'a>, UserError> {
+        let mut fields = record.split(',').map(str::trim);
+        let mut next = |name| fields.next().ok_or(UserError::InvalidRecord(name));
+        let username = next("missing username")?;
+        let email = next("missing email")?;
+        let sign_in_count = next("missing sign_in_count")?
+            .parse()
+            .map_err(|_| UserError::InvalidRecord("sign_in_count is not a number"))?;
+        let active = match next("missing active")? {
+            "true" => true,
+            "false" => false,
+            _ => return Err(UserError::InvalidRecord("active is not true or false")),
+        };
+        if fields.next().is_some() {
+            return Err(UserError::InvalidRecord("more than 4 fields"));
+        }
+
+        check_username(username).map_err(|reason| UserError::InvalidUsername {
+            username: String::from(username),
+            reason,
+        })?;
+        check_email(email).map_err(|reason| UserError::InvalidEmail {
+            email: String::from(email),
+            reason,
+        })?;
+        Ok(UserRef {
+            username,
+            email,
+            sign_in_count,
+            active,
+        })
+    }
+
+    pub fn username(&self) -> &'a str {
+        self.username
+    }
+
+    pub fn email(&self) -> &'a str {
+        self.email
+    }
+
+    pub fn sign_in_count(&self) -> u64 {
+        self.sign_in_count
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    // copies and normalizes, both fields are already checked
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_owned(&self) -> User {
+        User {
+            username: Username(self.username.to_ascii_lowercase()),
+            email: Email(self.email.to_lowercase()),
+            sign_in_count: self.sign_in_count,
+            active: self.active,
+        }
+    }
+}
+
+/// Parses CSV text, one user per line, without copying any strings
+///
+/// Blank lines and a leading `CSV_HEADER` line are skipped. Errors are
+/// wrapped in `UserError::AtLine`.
+pub fn parse_csv(input: &str) -> impl Iterator<Item = Result<UserRef<'_>, UserError>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|&(i, line)| !(line.trim().is_empty() || (i == 0 && line.trim() == CSV_HEADER)))
+        .map(|(i, line)| {
+            UserRef::parse_csv(line).map_err(|error| UserError::AtLine {
+                line: i + 1,
+                error: Box::new(error),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_and_normalizes() {
+        assert_eq!(
+            Email::parse(" Someone@Example.com ").unwrap().as_str(),
+            "someone@example.com"
+        );
+        for bad in &[
+            "",
+            "no-at",
+            "@example.com",
+            "a@b@c.com",
+            "a@example",
+            "a@.com",
+            "a b@c.com",
+        ] {
+            assert!(
+                matches!(Email::parse(bad), Err(UserError::InvalidEmail { .. })),
+                "{:?}",
+                bad
+            );
+        }
+
+        assert_eq!(
+            Username::parse("UserName123").unwrap().as_str(),
+            "username123"
+        );
+        for bad in &["ab", "1abc", "has space", "émile", "a".repeat(33).as_str()] {
+            assert!(
+                matches!(Username::parse(bad), Err(UserError::InvalidUsername { .. })),
+                "{:?}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn email_and_username_are_unique() {
+        let mut store = UserStore::new();
+        store
+            .register("someone@example.com", "username123")
+            .unwrap();
+        assert_eq!(
+            store.register("SOMEONE@example.com", "other"),
+            Err(UserError::EmailTaken(
+                Email::parse("someone@example.com").unwrap()
+            ))
+        );
+        assert!(matches!(
+            store.register("another@example.com", "Username123"),
+            Err(UserError::UsernameTaken(_))
+        ));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn queries_by_email_or_username() {
+        let mut store = UserStore::new();
+        store.register("a@example.com", "alice").unwrap();
+        store.register("b@example.com", "bob").unwrap();
+        assert_eq!(
+            store.by_email("B@example.com").unwrap().username.as_str(),
+            "bob"
+        );
+        assert_eq!(
+            store.by_username("ALICE").unwrap().email.as_str(),
+            "a@example.com"
+        );
+        assert!(store.by_email("c@example.com").is_none());
+        assert!(store.by_username("not valid").is_none());
+    }
+
+    #[test]
+    fn sign_ins_are_counted_until_deactivated() {
+        let mut store = UserStore::new();
+        store.register("a@example.com", "alice").unwrap();
+        assert_eq!(store.sign_in("alice").unwrap().sign_in_count, 1);
+        assert_eq!(store.sign_in("a@example.com").unwrap().sign_in_count, 2);
+
+        store.deactivate("alice").unwrap();
+        assert!(matches!(
+            store.sign_in("alice"),
+            Err(UserError::Inactive(_))
+        ));
+        assert_eq!(store.active().count(), 0);
+        assert_eq!(store.by_username("alice").unwrap().sign_in_count, 2);
+
+        store.reactivate("alice").unwrap();
+        assert_eq!(store.sign_in("alice").unwrap().sign_in_count, 3);
+
+        assert_eq!(
+            store.sign_in("nobody"),
+            Err(UserError::UnknownUser(String::from("nobody")))
+        );
+    }
+
+    const CSV: &str = "username,email,sign_in_count,active
+username123,Someone@Example.com,1,true
+
+anotherusername567, another@example.com ,12,false
+";
+
+    #[test]
+    fn parses_borrowed_users() {
+        let users: Vec<UserRef> = parse_csv(CSV).collect::<Result<_, _>>().unwrap();
+        assert_eq!(users.len(), 2);
+        // points into CSV, as is
+        assert_eq!(users[0].email(), "Someone@Example.com");
+        let at = CSV.find("username123").unwrap();
+        assert!(std::ptr::eq(users[0].username(), &CSV[at..at + 11]));
+        assert_eq!(users[1].email(), "another@example.com");
+        assert_eq!(users[1].sign_in_count(), 12);
+        assert!(!users[1].active());
+
+        let owned = users[0].to_owned();
+        assert_eq!(owned.email.as_str(), "someone@example.com");
+        assert_eq!(owned.sign_in_count, 1);
+
+        let mut store = UserStore::new();
+        for user in &users {
+            store.insert(user.to_owned()).unwrap();
+        }
+        assert_eq!(store.active().count(), 1);
+    }
+
+    #[test]
+    fn reports_the_bad_line() {
+        let input = "alice,a@example.com,1,true\nbob,b@example.com,lots,true\n";
+        let errors: Vec<UserError> = parse_csv(input).filter_map(Result::err).collect();
+        assert_eq!(
+            errors,
+            vec![UserError::AtLine {
+                line: 2,
+                error: Box::new(UserError::InvalidRecord("sign_in_count is not a number")),
+            }]
+        );
+        assert_eq!(errors[0].to_string(), "invalid user on line 2");
+        assert_eq!(
+            errors[0].source().unwrap().to_string(),
+            "invalid record: sign_in_count is not a number"
+        );
+
+        assert_eq!(
+            UserRef::parse_csv("alice,a@example.com,1"),
+            Err(UserError::InvalidRecord("missing active"))
+        );
+        assert!(matches!(
+            UserRef::parse_csv("alice,not-an-email,1,true"),
+            Err(UserError::InvalidEmail { .. })
+        ));
+        assert_eq!(
+            UserRef::parse_csv("alice,a@example.com,1,true,x"),
+            Err(UserError::InvalidRecord("more than 4 fields"))
+        );
+    }
+}
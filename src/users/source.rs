@@ -0,0 +1,423 @@
+// Username source
+/*
+`app_error::recoverable::handling` opens hello.txt and creates it when it's
+missing, `shortest()` reads it with fs::read_to_string; this is the same
+idea as something a program could actually use
+
+- the search path is an ordered list, highest priority first:
+  an explicit path, $USERNAME_FILE, $XDG_CONFIG_HOME/rust-example/username
+  (or ~/.config/rust-example/username), then hello.txt in the current dir
+- `standard` takes the env lookup and the current dir as arguments so
+  tests don't have to touch the process environment; `from_env` is the
+  real one
+- Policy decides what happens when a file isn't there:
+    - ReadOnly: try each path in order, the first that exists wins
+    - CreateIfMissing: same, but if none exists write the default to the
+      first path (parent dirs too) and use that
+    - Strict: only the first path counts, missing is an error, no fallback
+- a file that exists but can't be read, or holds an invalid name, is an
+  error under every policy; it doesn't fall through to the next path, or
+  a typo in the config would silently pick up some other file
+- the content is trimmed, must be one non-empty line and at most max_len
+  characters; not `Username::parse`, which lowercases and only allows
+  account names
+- every failure is an AppError: NotFound names the first path searched
+ */
+use crate::app_error::AppError;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const ENV_VAR: &str = "USERNAME_FILE";
+pub const DEFAULT_MAX_LEN: usize = 32;
+const APP_DIR: &str = "rust-example";
+const CONFIG_FILE: &str = "username";
+const CWD_FILE: &str = "hello.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Explicit,
+    EnvVar,
+    ConfigDir,
+    CurrentDir,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Policy {
+    ReadOnly,
+    // the username written when no file exists
+    CreateIfMissing(String),
+    Strict,
+}
+
+/// A username and where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loaded {
+    pub username: String,
+    pub path: PathBuf,
+    pub origin: Origin,
+    // true when CreateIfMissing wrote the file
+    pub created: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct UsernameSource {
+    paths: Vec<(Origin, PathBuf)>,
+    policy: Policy,
+    max_len: usize,
+}
+
+impl UsernameSource {
+    /// No paths yet, ReadOnly, DEFAULT_MAX_LEN
+    pub fn new() -> UsernameSource {
+        UsernameSource {
+            paths: Vec::new(),
+            policy: Policy::ReadOnly,
+            max_len: DEFAULT_MAX_LEN,
+        }
+    }
+
+    /// The search path from the notes at the top, with `env` looking up
+    /// variables; unset or empty variables are skipped
+    pub fn standard<F>(explicit: Option<&Path>, env: F, cwd: &Path) -> UsernameSource
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        let var = |name: &str| env(name).filter(|value| !value.is_empty());
+        let mut source = UsernameSource::new();
+        if let Some(path) = explicit {
+            source = source.path(Origin::Explicit, path);
+        }
+        if let Some(path) = var(ENV_VAR) {
+            source = source.path(Origin::EnvVar, path);
+        }
+        let config_dir = var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".config")));
+        if let Some(dir) = config_dir {
+            source = source.path(Origin::ConfigDir, dir.join(APP_DIR).join(CONFIG_FILE));
+        }
+        source.path(Origin::CurrentDir, cwd.join(CWD_FILE))
+    }
+
+    /// `standard` with the process environment and current dir
+    pub fn from_env(explicit: Option<&Path>) -> Result<UsernameSource, AppError> {
+        let cwd = std::env::current_dir()?;
+        Ok(UsernameSource::standard(
+            explicit,
+            |name| std::env::var_os(name),
+            &cwd,
+        ))
+    }
+
+    /// Adds a path after the ones already there
+    pub fn path<P: Into<PathBuf>>(mut self, origin: Origin, path: P) -> UsernameSource {
+        self.paths.push((origin, path.into()));
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> UsernameSource {
+        self.policy = policy;
+        self
+    }
+
+    pub fn max_len(mut self, max_len: usize) -> UsernameSource {
+        self.max_len = max_len;
+        self
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = (Origin, &Path)> {
+        self.paths
+            .iter()
+            .map(|(origin, path)| (*origin, path.as_path()))
+    }
+
+    pub fn load(&self) -> Result<Loaded, AppError> {
+        let (first_origin, first) = match self.paths.first() {
+            Some((origin, path)) => (*origin, path),
+            None => return Err(AppError::validation("no paths to search for a username")),
+        };
+        let candidates = match self.policy {
+            Policy::Strict => &self.paths[..1],
+            Policy::ReadOnly | Policy::CreateIfMissing(_) => &self.paths[..],
+        };
+        for (origin, path) in candidates {
+            match fs::read_to_string(path) {
+                Ok(text) => {
+                    return Ok(Loaded {
+                        username: self.validate(&text, path)?,
+                        path: path.clone(),
+                        origin: *origin,
+                        created: false,
+                    })
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(AppError::io(path, e)),
+            }
+        }
+        match &self.policy {
+            Policy::CreateIfMissing(default) => self.create(first_origin, first, default),
+            Policy::ReadOnly | Policy::Strict => Err(AppError::NotFound(first.clone())),
+        }
+    }
+
+    fn create(&self, origin: Origin, path: &Path, default: &str) -> Result<Loaded, AppError> {
+        let username = self.validate(default, path)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| AppError::io(dir, e))?;
+        }
+        // create_new: if something wrote the file since we looked, read that
+        let file = OpenOptions::new().write(true).create_new(true).open(path);
+        match file {
+            Ok(file) => {
+                fill(path, file, &username)?;
+                Ok(Loaded {
+                    username,
+                    path: path.to_path_buf(),
+                    origin,
+                    created: true,
+                })
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let text = fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+                Ok(Loaded {
+                    username: self.validate(&text, path)?,
+                    path: path.to_path_buf(),
+                    origin,
+                    created: false,
+                })
+            }
+            Err(e) => Err(AppError::io(path, e)),
+        }
+    }
+
+    fn validate(&self, text: &str, path: &Path) -> Result<String, AppError> {
+        let name = text.trim();
+        let reason = if name.is_empty() {
+            String::from("is empty")
+        } else if name.contains('\n') {
+            String::from("has more than one line")
+        } else if name.chars().count() > self.max_len {
+            format!("is longer than {} characters", self.max_len)
+        } else {
+            return Ok(String::from(name));
+        };
+        Err(AppError::validation(&format!(
+            "username in {} {}",
+            path.display(),
+            reason
+        )))
+    }
+}
+
+// create_new left an empty file at `path`; if writing to it fails, take it
+// away again, or every later load would stop at an empty file
+fn fill<W: Write>(path: &Path, mut file: W, username: &str) -> Result<(), AppError> {
+    let written = writeln!(file, "{}", username).and_then(|()| file.flush());
+    if let Err(e) = written {
+        let _ = fs::remove_file(path);
+        return Err(AppError::io(path, e));
+    }
+    Ok(())
+}
+
+impl Default for UsernameSource {
+    fn default() -> UsernameSource {
+        UsernameSource::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &Path)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.as_os_str().to_owned()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn search_order() {
+        let tmp = TempDir::new("username-source-order");
+        let explicit = tmp.join("explicit.txt");
+        let from_var = tmp.join("from-var.txt");
+        let xdg = tmp.join("xdg");
+        let home = tmp.join("home");
+
+        let source = UsernameSource::standard(
+            Some(&explicit),
+            env(&[
+                (ENV_VAR, &from_var),
+                ("XDG_CONFIG_HOME", &xdg),
+                ("HOME", &home),
+            ]),
+            tmp.path(),
+        );
+        let paths: Vec<_> = source.paths().collect();
+        assert_eq!(
+            paths,
+            vec![
+                (Origin::Explicit, explicit.as_path()),
+                (Origin::EnvVar, from_var.as_path()),
+                (
+                    Origin::ConfigDir,
+                    xdg.join("rust-example/username").as_path()
+                ),
+                (Origin::CurrentDir, tmp.join("hello.txt").as_path()),
+            ]
+        );
+
+        // falls back to ~/.config, empty variables don't count
+        let source = UsernameSource::standard(
+            None,
+            env(&[(ENV_VAR, Path::new("")), ("HOME", &home)]),
+            tmp.path(),
+        );
+        let paths: Vec<_> = source.paths().map(|(_, p)| p.to_path_buf()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                home.join(".config/rust-example/username"),
+                tmp.join("hello.txt")
+            ]
+        );
+
+        // the first file that exists wins
+        fs::write(tmp.join("hello.txt"), "from cwd\n").unwrap();
+        let source = UsernameSource::standard(Some(&explicit), env(&[]), tmp.path());
+        let loaded = source.load().unwrap();
+        assert_eq!(loaded.username, "from cwd");
+        assert_eq!(loaded.origin, Origin::CurrentDir);
+        assert!(!loaded.created);
+
+        fs::write(&explicit, "  ferris  \n").unwrap();
+        let loaded = source.load().unwrap();
+        assert_eq!(loaded.username, "ferris");
+        assert_eq!(loaded.origin, Origin::Explicit);
+        assert_eq!(loaded.path, explicit);
+    }
+
+    #[test]
+    fn policies() {
+        let tmp = TempDir::new("username-source-policies");
+        let config = tmp.join("config/rust-example/username");
+        let cwd = tmp.join("hello.txt");
+        let source = UsernameSource::new()
+            .path(Origin::ConfigDir, &config)
+            .path(Origin::CurrentDir, &cwd);
+
+        assert!(matches!(source.load(), Err(AppError::NotFound(ref p)) if *p == config));
+
+        fs::write(&cwd, "ferris").unwrap();
+        assert_eq!(source.load().unwrap().origin, Origin::CurrentDir);
+
+        // strict only looks at the first path
+        let strict = source.clone().policy(Policy::Strict);
+        assert!(matches!(strict.load(), Err(AppError::NotFound(ref p)) if *p == config));
+
+        // nothing is created while another path has a file
+        let create = source
+            .clone()
+            .policy(Policy::CreateIfMissing(String::from("guest")));
+        assert_eq!(create.load().unwrap().username, "ferris");
+        assert!(!config.exists());
+
+        fs::remove_file(&cwd).unwrap();
+        let loaded = create.load().unwrap();
+        assert_eq!(loaded.username, "guest");
+        assert_eq!(loaded.path, config);
+        assert!(loaded.created);
+        assert_eq!(fs::read_to_string(&config).unwrap(), "guest\n");
+
+        // second time it's just read
+        assert!(!create.load().unwrap().created);
+        assert_eq!(strict.load().unwrap().username, "guest");
+    }
+
+    #[test]
+    fn validation() {
+        let tmp = TempDir::new("username-source-validation");
+        let first = tmp.join("first.txt");
+        let second = tmp.join("second.txt");
+        fs::write(&second, "ferris").unwrap();
+        let source = UsernameSource::new()
+            .path(Origin::Explicit, &first)
+            .path(Origin::CurrentDir, &second)
+            .max_len(8);
+
+        // a bad file is an error, it doesn't fall through to the next
+        for (content, reason) in &[
+            ("", "is empty"),
+            (" \n\t\n", "is empty"),
+            ("ferris\ncrab", "has more than one line"),
+            ("ferristhecrab", "is longer than 8 characters"),
+        ] {
+            fs::write(&first, content).unwrap();
+            assert_eq!(
+                source.load().unwrap_err().to_string(),
+                format!("invalid: username in {} {}", first.display(), reason)
+            );
+        }
+        // characters, not bytes
+        fs::write(&first, "émilieé").unwrap();
+        assert_eq!(source.load().unwrap().username, "émilieé");
+
+        // the default has to pass too, and nothing is written if it doesn't
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+        let create = source.policy(Policy::CreateIfMissing(String::from(" ")));
+        assert!(matches!(create.load(), Err(AppError::Validation(_))));
+        assert!(!first.exists());
+
+        assert!(matches!(
+            UsernameSource::new().load(),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn unreadable_is_an_error() {
+        let tmp = TempDir::new("username-source-unreadable");
+        let dir = tmp.join("a-directory");
+        fs::create_dir(&dir).unwrap();
+        fs::write(tmp.join("hello.txt"), "ferris").unwrap();
+        let source = UsernameSource::new()
+            .path(Origin::Explicit, &dir)
+            .path(Origin::CurrentDir, tmp.join("hello.txt"));
+        assert!(matches!(source.load(), Err(AppError::Io { path: Some(ref p), .. }) if *p == dir));
+    }
+
+    struct DiskFull;
+
+    impl Write for DiskFull {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("no space left on device"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_write_leaves_no_empty_file() {
+        let tmp = TempDir::new("username-source-failed-write");
+        let path = tmp.join("username");
+        fs::write(&path, "").unwrap();
+        let e = fill(&path, DiskFull, "guest").unwrap_err();
+        assert_eq!(e.to_string(), format!("I/O error on {}", path.display()));
+        assert!(!path.exists());
+
+        // so the next load tries again
+        let source = UsernameSource::new()
+            .path(Origin::ConfigDir, &path)
+            .policy(Policy::CreateIfMissing(String::from("guest")));
+        assert!(source.load().unwrap().created);
+    }
+}